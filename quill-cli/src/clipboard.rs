@@ -0,0 +1,122 @@
+//! System clipboard access for the terminal
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Result};
+
+/// Local clipboard programs, tried in order, with the environment variable
+/// that must be set for each to be usable
+const PROVIDERS: &[(&str, &[&str], Option<&str>)] = &[
+    ("pbcopy", &[], None),
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+    ("clip.exe", &[], None),
+];
+
+/// Copy text with OSC 52, which reaches the local terminal over SSH and
+/// through tmux, then also with a local clipboard program when one exists.
+/// Returns how the text was copied, for the status bar.
+pub fn copy(text: &str) -> Result<String> {
+    let osc52 = write_osc52(text);
+    let local = if over_ssh() {
+        Err(anyhow!("No local clipboard over SSH"))
+    } else {
+        copy_with_provider(text)
+    };
+    match (local, osc52) {
+        (Ok(()), _) => Ok("copied to clipboard".to_string()),
+        // Terminals that don't support OSC 52 ignore it without saying so
+        (Err(_), Ok(())) => Ok("sent to clipboard (OSC 52)".to_string()),
+        (Err(_), Err(e)) => Err(e),
+    }
+}
+
+fn over_ssh() -> bool {
+    std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+}
+
+/// Ask the terminal to set its clipboard with an OSC 52 escape sequence
+fn write_osc52(text: &str) -> Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux only forwards the sequence when wrapped in a passthrough
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    };
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Pipe text into the first local clipboard program that runs
+fn copy_with_provider(text: &str) -> Result<()> {
+    for (program, args, env) in PROVIDERS {
+        if env.is_some_and(|var| std::env::var_os(var).is_none()) {
+            continue;
+        }
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    bail!("No clipboard program found (pbcopy, wl-copy, xclip, xsel, clip.exe)")
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_rfc4648_vectors() {
+        for (input, expected) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(input.as_bytes()), expected, "{:?}", input);
+        }
+        assert_eq!(base64(&[0xff, 0xfe]), "//4=");
+    }
+}
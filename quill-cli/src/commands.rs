@@ -0,0 +1,414 @@
+//! Headless subcommands for scripting annotations
+
+use std::io::{ErrorKind, Write};
+use std::process::ExitCode;
+
+use anyhow::{bail, Context, Result};
+use clap::{Subcommand, ValueEnum};
+
+use quill_core::sidecar::orphaned_warning;
+use quill_core::template::DEFAULT_TEMPLATE_NAME;
+use quill_core::{
+    budget_warning, check, estimate_tokens, general_label, join_prompts, outline, to_json,
+    Annotation, Category, Document, ExcerptContext, PromptOptions, Scope, Severity, TextRange,
+    Thresholds,
+};
+
+use crate::io;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List annotations on a file
+    List {
+        file: String,
+        /// Hide resolved annotations
+        #[arg(long)]
+        unresolved: bool,
+    },
+    /// Export a file's annotations
+    Export {
+        file: String,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Prompt template for the md format, by name or path
+        #[arg(long, default_value = DEFAULT_TEMPLATE_NAME)]
+        template: String,
+        /// Write to this path instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Print the revision prompt for a file
+    Prompt {
+        file: String,
+        /// Prompt template, by name or path
+        #[arg(long, default_value = DEFAULT_TEMPLATE_NAME)]
+        template: String,
+        /// Include excerpts with this much context (e.g. 2s, 1p) instead of the full text
+        #[arg(long)]
+        excerpts: Option<ExcerptContext>,
+        /// Split into one prompt per group of sections above this many tokens
+        #[arg(long)]
+        budget: Option<usize>,
+        /// Ask for revisions as JSON keyed by annotation ID, for review with R in the TUI
+        #[arg(long)]
+        structured: bool,
+    },
+    /// Add an annotation to a file
+    Add {
+        file: String,
+        /// Byte offsets of the annotated text, as START:END, or a single
+        /// offset for an insertion point
+        #[arg(long, value_parser = parse_range, required_unless_present_any = ["section", "document"])]
+        range: Option<TextRange>,
+        /// Comment on the section under this heading instead of a range
+        #[arg(long, conflicts_with = "range")]
+        section: Option<String>,
+        /// Comment on the whole document instead of a range
+        #[arg(long, conflicts_with_all = ["range", "section"])]
+        document: bool,
+        #[arg(long, default_value = "should-fix")]
+        severity: Severity,
+        #[arg(long)]
+        category: Option<Category>,
+        #[arg(long)]
+        comment: String,
+        #[arg(long, env = "USER")]
+        author: Option<String>,
+    },
+    /// Mark an annotation resolved (or unresolved with --undo)
+    Resolve {
+        file: String,
+        /// Annotation ID or a unique prefix of it
+        id: String,
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Exit non-zero when unresolved annotations exceed a threshold
+    Check {
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Most unresolved must-fix annotations allowed
+        #[arg(long, default_value_t = 0)]
+        max_must_fix: usize,
+        /// Most unresolved should-fix annotations allowed (no limit by default)
+        #[arg(long)]
+        max_should_fix: Option<usize>,
+        /// Most unresolved consider annotations allowed (no limit by default)
+        #[arg(long)]
+        max_consider: Option<usize>,
+        /// Print the reports as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Json,
+    Md,
+}
+
+/// Run a subcommand against a file and its saved annotations
+pub fn run(command: Command) -> Result<ExitCode> {
+    let mut out = std::io::stdout().lock();
+    match execute(command, &mut out) {
+        // Output piped into a command that exited early, such as `head`
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) =>
+        {
+            Ok(ExitCode::SUCCESS)
+        }
+        result => result,
+    }
+}
+
+fn execute(command: Command, out: &mut impl Write) -> Result<ExitCode> {
+    match command {
+        Command::List { file, unresolved } => {
+            let doc = load(&file)?;
+            for ann in doc.annotations_sorted().into_iter().chain(&doc.orphaned) {
+                if unresolved && ann.is_resolved {
+                    continue;
+                }
+                writeln!(out, "{}", list_line(&doc, ann))?;
+            }
+        }
+        Command::Export {
+            file,
+            format,
+            template,
+            output,
+        } => {
+            let doc = load(&file)?;
+            let text = match format {
+                Format::Json => to_json(&doc)?,
+                Format::Md => io::load_template(&template)?.render(&doc, &PromptOptions::default()),
+            };
+            match output {
+                Some(path) => std::fs::write(&path, text)
+                    .with_context(|| format!("Failed to write {}", path))?,
+                None => writeln!(out, "{}", text)?,
+            }
+        }
+        Command::Prompt {
+            file,
+            template,
+            excerpts,
+            budget,
+            structured,
+        } => {
+            let doc = load(&file)?;
+            let template = io::load_template(&template)?;
+            let options = PromptOptions {
+                excerpts,
+                token_budget: budget,
+                structured,
+                ..PromptOptions::default()
+            };
+            let prompts = template.render_all(&doc, &options);
+            writeln!(out, "{}", join_prompts(&prompts))?;
+
+            if excerpts.is_some() || budget.is_some() {
+                let tokens: Vec<String> = prompts
+                    .iter()
+                    .map(|p| format!("~{}", estimate_tokens(p)))
+                    .collect();
+                eprintln!("{} prompt(s), {} tokens", prompts.len(), tokens.join(" + "));
+            }
+            if let Some(warning) = budget_warning(&prompts, budget) {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+        Command::Add {
+            file,
+            range,
+            section,
+            document,
+            severity,
+            category,
+            comment,
+            author,
+        } => {
+            let mut doc = load(&file)?;
+            let (range, scope) = match (range, section) {
+                (Some(range), _) => (range, Scope::Range),
+                (None, Some(title)) => (section_range(&doc, &title)?, Scope::Section),
+                (None, None) if document => (TextRange::new(0, 0), Scope::Document),
+                (None, None) => bail!("Give --range, --section or --document"),
+            };
+            let Some(text) = doc.content.get(range.start_offset..range.end_offset) else {
+                bail!(
+                    "Range {}:{} is outside the document or splits a character",
+                    range.start_offset,
+                    range.end_offset
+                );
+            };
+            let mut ann = Annotation::new(range, text.to_string(), comment);
+            ann.scope = scope;
+            ann.severity = severity;
+            ann.category = category;
+            ann.author = author;
+            let id = ann.id;
+            doc.add_annotation(ann);
+            io::save_sidecar(&doc)?;
+            writeln!(out, "{}", id)?;
+        }
+        Command::Resolve { file, id, undo } => {
+            let mut doc = load(&file)?;
+            let ann = find_annotation(&mut doc, &id)?;
+            ann.is_resolved = !undo;
+            io::save_sidecar(&doc)?;
+        }
+        Command::Check {
+            files,
+            max_must_fix,
+            max_should_fix,
+            max_consider,
+            json,
+        } => {
+            let thresholds = Thresholds {
+                must_fix: Some(max_must_fix),
+                should_fix: max_should_fix,
+                consider: max_consider,
+            };
+            let reports = files
+                .iter()
+                .map(|file| Ok(check(&io::load_file(file)?, &thresholds)))
+                .collect::<Result<Vec<_>>>()?;
+
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&reports)?)?;
+            } else {
+                for report in &reports {
+                    write!(out, "{}", report.to_text())?;
+                }
+            }
+            if reports.iter().any(|r| !r.passed) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Load a file and its annotations, warning about any its sidecar could not
+/// restore; they are still saved back with the rest
+fn load(file: &str) -> Result<Document> {
+    let doc = io::load_file(file)?;
+    if let Some(warning) = orphaned_warning(&doc) {
+        eprintln!("{}: {}", file, warning);
+    }
+    Ok(doc)
+}
+
+/// One line of `quill list` output
+fn list_line(doc: &Document, ann: &Annotation) -> String {
+    let id = ann.id.to_string();
+    let line = if doc.orphaned.iter().any(|a| a.id == ann.id) {
+        "?".to_string()
+    } else {
+        doc.line_number(ann.range.start_offset).to_string()
+    };
+    let scope = if ann.is_general() {
+        format!("[{}] ", general_label(doc, ann))
+    } else {
+        String::new()
+    };
+    format!(
+        "{}  L{:<4} {:<10} {:<9} {}{}{}",
+        &id[..8],
+        line,
+        ann.severity.name(),
+        ann.category.map(|c| c.as_str()).unwrap_or("-"),
+        if ann.is_resolved { "[resolved] " } else { "" },
+        scope,
+        ann.comment.replace('\n', " ")
+    )
+}
+
+/// Find an annotation, orphaned or not, by a unique prefix of its ID
+fn find_annotation<'a>(doc: &'a mut Document, prefix: &str) -> Result<&'a mut Annotation> {
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        bail!("Invalid ID prefix: {:?}", prefix);
+    }
+    let prefix = prefix.to_ascii_lowercase();
+    let mut matches: Vec<&mut Annotation> = doc
+        .annotations
+        .iter_mut()
+        .chain(doc.orphaned.iter_mut())
+        .filter(|a| a.id.to_string().starts_with(&prefix))
+        .collect();
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => bail!("No annotation with ID {}", prefix),
+        n => bail!("ID prefix {} matches {} annotations", prefix, n),
+    }
+}
+
+/// Range of the line of the heading titled `title`, ignoring case
+fn section_range(doc: &Document, title: &str) -> Result<TextRange> {
+    let Some(heading) = outline::parse_headings(&doc.content)
+        .into_iter()
+        .find(|h| h.title.eq_ignore_ascii_case(title.trim()))
+    else {
+        bail!("No heading titled {}", title);
+    };
+    Ok(heading.line_range(&doc.content))
+}
+
+fn parse_range(s: &str) -> Result<TextRange, String> {
+    let (start, end) = s.split_once(':').unwrap_or((s, s));
+    let start: usize = start
+        .parse()
+        .map_err(|_| format!("Invalid start offset: {}", start))?;
+    let end: usize = end
+        .parse()
+        .map_err(|_| format!("Invalid end offset: {}", end))?;
+    if start > end {
+        return Err(format!("Start must be before end: {}", s));
+    }
+    Ok(TextRange::new(start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc_with(content: &str, ranges: &[(usize, usize)]) -> Document {
+        let mut doc = Document::new("Test".to_string(), content.to_string());
+        for &(start, end) in ranges {
+            let text = content[start..end].to_string();
+            doc.add_annotation(Annotation::new(
+                TextRange::new(start, end),
+                text,
+                "fix".to_string(),
+            ));
+        }
+        doc
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("6:11"), Ok(TextRange::new(6, 11)));
+        assert_eq!(parse_range("4"), Ok(TextRange::new(4, 4)));
+        assert!(parse_range("11:6").is_err());
+        assert!(parse_range("a:3").is_err());
+        assert!(parse_range("").is_err());
+    }
+
+    #[test]
+    fn test_find_annotation() {
+        let mut doc = doc_with("Hello world", &[(0, 5), (6, 11)]);
+        doc.orphaned.push(Annotation::new(
+            TextRange::new(0, 3),
+            "Hey".to_string(),
+            "gone".to_string(),
+        ));
+        let id = doc.annotations[1].id.to_string();
+
+        let found = find_annotation(&mut doc, &id[..8].to_ascii_uppercase()).unwrap();
+        assert_eq!(found.selected_text, "world");
+        let orphan = doc.orphaned[0].id.to_string();
+        assert_eq!(find_annotation(&mut doc, &orphan).unwrap().comment, "gone");
+
+        assert!(find_annotation(&mut doc, "").is_err());
+        assert!(find_annotation(&mut doc, "xyz").is_err());
+        assert!(find_annotation(&mut doc, "-").is_err());
+    }
+
+    #[test]
+    fn test_section_range() {
+        let doc = doc_with("# Intro\ntext\n## Next Steps\nmore\n", &[]);
+        assert_eq!(
+            section_range(&doc, "next steps").unwrap(),
+            TextRange::new(13, 26)
+        );
+        assert!(section_range(&doc, "Missing").is_err());
+    }
+
+    #[test]
+    fn test_list_line() {
+        let mut doc = doc_with("one\ntwo", &[(4, 7)]);
+        doc.annotations[0].comment = "first\nsecond".to_string();
+        doc.orphaned.push(Annotation::new(
+            TextRange::new(9, 12),
+            "old".to_string(),
+            "stale".to_string(),
+        ));
+
+        let id = doc.annotations[0].id.to_string();
+        let line = list_line(&doc, &doc.annotations[0]);
+        assert_eq!(
+            line,
+            format!("{}  L2    should-fix -         first second", &id[..8])
+        );
+        assert!(list_line(&doc, &doc.orphaned[0]).contains("L?    should-fix"));
+
+        let mut general =
+            Annotation::new(TextRange::new(0, 0), String::new(), "overall".to_string());
+        general.scope = Scope::Document;
+        general.is_resolved = true;
+        assert!(list_line(&doc, &general).ends_with("[resolved] [Whole document] overall"));
+    }
+}
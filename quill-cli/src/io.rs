@@ -1,10 +1,15 @@
 //! File I/O for native CLI
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use quill_core::Document;
+use quill_core::send::response_path;
+use quill_core::sidecar::{apply_sidecar, sidecar_path, to_sidecar_json};
+use quill_core::template::DEFAULT_TEMPLATE_NAME;
+use quill_core::{App, Config, Document, Platform, Template};
+
+use crate::send::Running;
 
 /// Load a text file and create a Document
 pub fn load_file(path: &str) -> Result<Document> {
@@ -26,7 +31,31 @@ pub fn load_file(path: &str) -> Result<Document> {
         .map(|s| s.to_string_lossy().to_string())
         .unwrap_or_else(|| "Untitled".to_string());
 
-    Ok(Document::with_file_info(title, content, filepath, filename))
+    let mut doc = Document::with_file_info(title, content, filepath.clone(), filename);
+
+    // Restore annotations saved with :w
+    let sidecar = sidecar_path(&filepath);
+    if Path::new(&sidecar).exists() {
+        let json =
+            fs::read_to_string(&sidecar).with_context(|| format!("Failed to read {}", sidecar))?;
+        apply_sidecar(&mut doc, &json).with_context(|| format!("Failed to parse {}", sidecar))?;
+    }
+
+    Ok(doc)
+}
+
+/// Write a document's annotations to its sidecar file
+pub fn save_sidecar(doc: &Document) -> Result<PathBuf> {
+    let filepath = doc
+        .filepath
+        .as_deref()
+        .context("Document has no file path")?;
+    let path = PathBuf::from(sidecar_path(filepath));
+    let json = to_sidecar_json(doc).context("Failed to serialize annotations")?;
+
+    fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
 }
 
 /// Get the ~/.quill directory path, creating it if needed
@@ -42,16 +71,171 @@ pub fn quill_dir() -> Result<PathBuf> {
     Ok(quill_dir)
 }
 
-/// Export document to ~/.quill/document.json
-pub fn export_document(doc: &Document) -> Result<PathBuf> {
-    let quill_dir = quill_dir()?;
-    let export_path = quill_dir.join("document.json");
+/// Write exported content to a file in ~/.quill
+pub fn save_export(filename: &str, contents: &str) -> Result<PathBuf> {
+    let export_path = quill_dir()?.join(filename);
 
-    let json = quill_core::to_json(doc)
-        .context("Failed to serialize document")?;
-
-    fs::write(&export_path, json)
+    fs::write(&export_path, contents)
         .with_context(|| format!("Failed to write {}", export_path.display()))?;
 
     Ok(export_path)
 }
+
+/// Write edited document text back to its file
+pub fn save_document(doc: &Document) -> Result<PathBuf> {
+    let filepath = doc
+        .filepath
+        .as_deref()
+        .context("Document has no file path")?;
+
+    fs::write(filepath, &doc.content).with_context(|| format!("Failed to write {}", filepath))?;
+
+    Ok(PathBuf::from(filepath))
+}
+
+/// Write a send command's output next to the document
+pub fn save_response(doc: &Document, text: &str) -> Result<PathBuf> {
+    let filepath = doc
+        .filepath
+        .as_deref()
+        .context("Document has no file path")?;
+    let path = PathBuf::from(response_path(filepath));
+
+    fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Platform I/O backed by the local filesystem
+#[derive(Default)]
+pub struct NativePlatform {
+    /// Command started by the send action
+    running: Option<Running>,
+}
+
+impl NativePlatform {
+    pub fn command_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Pass new output from the send command to the app, and its exit once done
+    pub fn poll_command(&mut self, app: &mut App) {
+        let Some(running) = self.running.as_mut() else {
+            return;
+        };
+        let (output, exit) = running.poll();
+        if !output.is_empty() {
+            app.push_output(&output);
+        }
+        if let Some(error) = exit {
+            self.running = None;
+            app.finish_output(error, self);
+        }
+    }
+}
+
+impl Platform for NativePlatform {
+    fn open_file(&mut self, path: &str) -> Result<Document> {
+        load_file(path)
+    }
+
+    fn read_text(&mut self, path: &str) -> Result<String> {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))
+    }
+
+    fn save_export(&mut self, filename: &str, contents: &str) -> Result<String> {
+        save_export(filename, contents).map(|path| path.display().to_string())
+    }
+
+    fn save_document(&mut self, doc: &Document) -> Result<String> {
+        save_document(doc).map(|path| path.display().to_string())
+    }
+
+    fn save_sidecar(&mut self, doc: &Document) -> Result<String> {
+        save_sidecar(doc).map(|path| path.display().to_string())
+    }
+
+    fn run_command(&mut self, command: &str, input: &str) -> Result<()> {
+        self.running = Some(Running::spawn(command, input)?);
+        Ok(())
+    }
+
+    fn cancel_command(&mut self) {
+        if let Some(running) = self.running.as_mut() {
+            running.kill();
+        }
+    }
+
+    fn save_response(&mut self, doc: &Document, text: &str) -> Result<String> {
+        save_response(doc, text).map(|path| path.display().to_string())
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<String> {
+        crate::clipboard::copy(text)
+    }
+}
+
+/// Load user configuration from ~/.quill/config.toml, using defaults if it doesn't exist
+pub fn load_config() -> Result<Config> {
+    let home = dirs::home_dir().context("Could not find home directory")?;
+    let config_path = home.join(".quill").join("config.toml");
+    if !config_path.exists() {
+        return Ok(Config::default());
+    }
+
+    let text = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Failed to parse {}", config_path.display()))
+}
+
+/// Read prompt templates from ~/.quill/templates/*.tmpl, named by file stem
+pub fn load_templates() -> Result<Vec<(String, String)>> {
+    let home = dirs::home_dir().context("Could not find home directory")?;
+    let dir = home.join(".quill").join("templates");
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "tmpl") {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let source = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            templates.push((name, source));
+        }
+    }
+    templates.sort();
+    Ok(templates)
+}
+
+/// Find a prompt template by file path, or by name in the config file or
+/// the templates directory
+pub fn load_template(name: &str) -> Result<Template> {
+    if name == DEFAULT_TEMPLATE_NAME {
+        return Ok(Template::default());
+    }
+
+    let path = Path::new(name);
+    let (name, source) = if path.is_file() {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        (stem, source)
+    } else if let Some(source) = load_config()?.templates.remove(name) {
+        (name.to_string(), source)
+    } else {
+        load_templates()?
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| format!("Unknown template: {}", name))?
+    };
+    Template::parse(&name, &source).map_err(|e| anyhow!("Template {}: {}", name, e))
+}
@@ -1,42 +1,90 @@
 //! Quill CLI - Terminal-based text annotation tool
 
+mod clipboard;
+mod commands;
 mod io;
+mod send;
 mod ui;
 
 use std::io::stdout;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use clap::Parser;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::prelude::*;
 
-use quill_core::{generate_prompt, App, Category, Focus, InputTarget, Mode, Severity};
+use quill_core::{App, Focus, Key, KeyChord, Mode};
 
-fn main() -> Result<()> {
-    // Get file path from args
-    let args: Vec<String> = std::env::args().collect();
-    let file_path = args.get(1);
+/// Annotate text documents and turn the feedback into LLM revision prompts
+#[derive(Parser)]
+#[command(name = "quill", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    /// File to open in the editor
+    file: Option<String>,
 
+    #[command(subcommand)]
+    command: Option<commands::Command>,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    match cli.command {
+        Some(command) => commands::run(command),
+        None => run_tui(cli.file.as_deref()).map(|()| ExitCode::SUCCESS),
+    }
+}
+
+fn run_tui(file_path: Option<&str>) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
     let mut app = App::new();
+    app.author = std::env::var("USER").ok();
+
+    // Default to a theme the terminal can show; config may override it
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let truecolor = std::env::var("COLORTERM").is_ok_and(|v| v == "truecolor" || v == "24bit");
+    if no_color {
+        app.set_theme("no-color");
+    } else if !truecolor {
+        app.set_theme("16-color");
+    }
+
+    // Templates load first so the config can select one
+    let mut config_errors = Vec::new();
+    match io::load_templates() {
+        Ok(templates) => {
+            for (name, source) in templates {
+                if let Err(e) = app.add_template(&name, &source) {
+                    config_errors.push(format!("Template {}: {}", name, e));
+                }
+            }
+        }
+        Err(e) => config_errors.push(e.to_string()),
+    }
+    match io::load_config() {
+        Ok(config) => config_errors.extend(app.apply_config(&config)),
+        Err(e) => config_errors.push(e.to_string()),
+    }
 
     // Load file if provided
     if let Some(path) = file_path {
         match io::load_file(path) {
-            Ok(doc) => {
-                app.load_document(doc);
-                app.set_status(&format!("Loaded {}", path));
-            }
+            Ok(doc) => app.open_document(doc, path),
             Err(e) => {
                 app.set_status(&format!("Error: {}", e));
             }
@@ -44,13 +92,20 @@ fn main() -> Result<()> {
     } else {
         app.set_status("No file loaded. Pass a file path as argument.");
     }
+    if let Some(error) = config_errors.first() {
+        app.set_status(&format!("Config: {}", error));
+    }
 
     // Main loop
     let res = run_app(&mut terminal, &mut app);
 
     // Restore terminal
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     terminal.show_cursor()?;
 
     if let Err(e) = res {
@@ -60,257 +115,151 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
-    while app.running {
-        terminal.draw(|f| ui::draw(f, app))?;
+/// Two clicks at the same spot within this interval select a word
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
 
-        if let Event::Key(key) = event::read()? {
-            // Clear status on any key
-            app.clear_status();
+/// How often output from a running send command is picked up
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
-            match app.mode {
-                Mode::Normal => handle_normal_mode(app, key.code, key.modifiers),
-                Mode::Visual => handle_visual_mode(app, key.code),
-                Mode::Input => handle_input_mode(app, key.code),
-                Mode::SeverityPicker => handle_severity_picker(app, key.code),
-                Mode::CategoryPicker => handle_category_picker(app, key.code),
-                Mode::Help => {
-                    app.mode = Mode::Normal;
-                }
-            }
-        }
-    }
-    Ok(())
+/// Lines moved per scroll-wheel notch
+const SCROLL_LINES: usize = 3;
+
+/// Position and time of the last left click, for double-click detection
+struct LastClick {
+    at: Instant,
+    column: u16,
+    row: u16,
 }
 
-fn handle_normal_mode(app: &mut App, code: KeyCode, _modifiers: KeyModifiers) {
-    match code {
-        KeyCode::Char('q') => app.running = false,
-        KeyCode::Char('?') => app.mode = Mode::Help,
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    let mut screen = ui::ScreenMap::default();
+    let mut last_click: Option<LastClick> = None;
+    let mut platform = io::NativePlatform::default();
 
-        // Navigation
-        KeyCode::Char('j') | KeyCode::Down => {
-            if app.focus == Focus::Editor {
-                app.move_down();
-            } else {
-                app.next_annotation();
-            }
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            if app.focus == Focus::Editor {
-                app.move_up();
-            } else {
-                app.prev_annotation();
+    while app.running {
+        terminal.draw(|f| screen = ui::draw(f, app))?;
+
+        // Keep redrawing while a send command streams output
+        if platform.command_running() {
+            platform.poll_command(app);
+            if !event::poll(COMMAND_POLL_INTERVAL)? {
+                continue;
             }
         }
-        KeyCode::Char('h') | KeyCode::Left => {
-            app.move_left();
-        }
-        KeyCode::Char('l') | KeyCode::Right => {
-            app.move_right();
-        }
-        KeyCode::Char('g') => {
-            app.move_to_top();
-        }
-        KeyCode::Char('G') => {
-            app.move_to_bottom();
-        }
-
-        // Annotation navigation
-        KeyCode::Char(']') => app.next_annotation(),
-        KeyCode::Char('[') => app.prev_annotation(),
 
-        // Visual mode
-        KeyCode::Char('v') => app.enter_visual_mode(),
-
-        // Annotation actions
-        KeyCode::Char('d') => {
-            app.delete_selected_annotation();
-        }
-        KeyCode::Char('r') => {
-            app.toggle_selected_resolved();
+        let event = event::read()?;
+        if let Event::Mouse(mouse) = event {
+            handle_mouse(app, &screen, &mut last_click, mouse);
         }
 
-        // Focus toggle
-        KeyCode::Tab => app.toggle_focus(),
-
-        // Export
-        KeyCode::Char('e') => {
-            if let Some(doc) = &app.document {
-                match io::export_document(doc) {
-                    Ok(path) => app.set_status(&format!("Exported to {}", path.display())),
-                    Err(e) => app.set_status(&format!("Export failed: {}", e)),
-                }
-            }
-        }
-        KeyCode::Char('E') => {
-            if let Some(doc) = &app.document {
-                let prompt = generate_prompt(doc);
-                // In a real app, we'd copy to clipboard or show in a pane
-                app.set_status(&format!("Prompt generated ({} chars)", prompt.len()));
+        if let Event::Key(key) = event {
+            if let Some(chord) = key_chord(&key) {
+                app.handle_key(chord, &mut platform);
             }
         }
-
-        // Open file
-        KeyCode::Char('o') => {
-            app.input_buffer.clear();
-            app.input_target = InputTarget::FilePath;
-            app.mode = Mode::Input;
-        }
-
-        _ => {}
     }
+    Ok(())
 }
 
-fn handle_visual_mode(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.selection_start = None;
-            app.selection_end = None;
-        }
-        KeyCode::Char('j') | KeyCode::Down => {
-            app.move_down();
-            app.update_selection();
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            app.move_up();
-            app.update_selection();
-        }
-        KeyCode::Char('h') | KeyCode::Left => {
-            app.move_left();
-            app.update_selection();
-        }
-        KeyCode::Char('l') | KeyCode::Right => {
-            app.move_right();
-            app.update_selection();
-        }
-        KeyCode::Char('w') => {
-            app.move_word_forward();
-            app.update_selection();
-        }
-        KeyCode::Char('b') => {
-            app.move_word_back();
-            app.update_selection();
-        }
-        KeyCode::Char('a') => {
-            app.start_annotation();
-        }
-        _ => {}
+fn handle_mouse(
+    app: &mut App,
+    screen: &ui::ScreenMap,
+    last_click: &mut Option<LastClick>,
+    mouse: MouseEvent,
+) {
+    if !matches!(app.mode, Mode::Normal | Mode::Visual) {
+        return;
     }
-}
 
-fn handle_input_mode(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.input_buffer.clear();
-            app.pending_range = None;
-        }
-        KeyCode::Enter => {
-            match app.input_target {
-                InputTarget::Comment => {
-                    app.complete_annotation();
-                }
-                InputTarget::FilePath => {
-                    let path = app.input_buffer.clone();
-                    match io::load_file(&path) {
-                        Ok(doc) => {
-                            app.load_document(doc);
-                            app.set_status(&format!("Loaded {}", path));
-                        }
-                        Err(e) => {
-                            app.set_status(&format!("Error: {}", e));
-                        }
-                    }
-                    app.input_buffer.clear();
-                    app.mode = Mode::Normal;
+    let position = Position::new(mouse.column, mouse.row);
+    let in_editor = screen.editor.contains(position);
+    let in_sidebar = screen.sidebar.contains(position);
+
+    // Translate screen coordinates through the scroll and wrap model
+    let editor_position = || {
+        let row = screen.editor_scroll + mouse.row.saturating_sub(screen.editor.y) as usize;
+        let col = mouse.column.saturating_sub(screen.editor.x) as usize;
+        screen.layout.position_at(row, col)
+    };
+
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) if in_editor => {
+            let double = last_click.as_ref().is_some_and(|c| {
+                c.at.elapsed() < DOUBLE_CLICK_INTERVAL
+                    && c.column == mouse.column
+                    && c.row == mouse.row
+            });
+            *last_click = Some(LastClick {
+                at: Instant::now(),
+                column: mouse.column,
+                row: mouse.row,
+            });
+
+            app.focus = Focus::Editor;
+            if let Some((row, col)) = editor_position() {
+                app.mode = Mode::Normal;
+                app.selection_start = None;
+                app.selection_end = None;
+                app.place_cursor(row, col);
+                if double {
+                    app.select_word_at_cursor();
+                    *last_click = None;
                 }
             }
         }
-        KeyCode::Backspace => {
-            app.input_buffer.pop();
-        }
-        KeyCode::Char(c) => {
-            app.input_buffer.push(c);
-        }
-        _ => {}
-    }
-}
-
-fn handle_severity_picker(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.pending_range = None;
-        }
-        KeyCode::Char('j') | KeyCode::Down => {
-            app.severity_selected = (app.severity_selected + 1) % Severity::all().len();
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            let len = Severity::all().len();
-            app.severity_selected = if app.severity_selected == 0 {
-                len - 1
-            } else {
-                app.severity_selected - 1
-            };
-        }
-        KeyCode::Enter => {
-            app.pending_severity = Severity::all()[app.severity_selected];
-            app.mode = Mode::CategoryPicker;
+        MouseEventKind::Down(MouseButton::Left) if in_sidebar => {
+            app.focus = Focus::Sidebar;
+            let row = mouse.row.saturating_sub(screen.sidebar.y) as usize;
+            if let Some(Some(index)) = screen.sidebar_rows.get(row) {
+                app.select_annotation(*index);
+            }
         }
-        // Quick select
-        KeyCode::Char('1') => {
-            app.pending_severity = Severity::MustFix;
-            app.mode = Mode::CategoryPicker;
+        MouseEventKind::Drag(MouseButton::Left) if in_editor => {
+            if let Some((row, col)) = editor_position() {
+                app.extend_selection_to(row, col);
+            }
         }
-        KeyCode::Char('2') => {
-            app.pending_severity = Severity::ShouldFix;
-            app.mode = Mode::CategoryPicker;
+        MouseEventKind::ScrollDown if in_sidebar => app.next_annotation(),
+        MouseEventKind::ScrollUp if in_sidebar => app.prev_annotation(),
+        MouseEventKind::ScrollDown => {
+            for _ in 0..SCROLL_LINES {
+                app.move_down();
+            }
+            app.update_selection();
         }
-        KeyCode::Char('3') => {
-            app.pending_severity = Severity::Consider;
-            app.mode = Mode::CategoryPicker;
+        MouseEventKind::ScrollUp => {
+            for _ in 0..SCROLL_LINES {
+                app.move_up();
+            }
+            app.update_selection();
         }
         _ => {}
     }
 }
 
-fn handle_category_picker(app: &mut App, code: KeyCode) {
-    let total = Category::all().len() + 1; // +1 for "None"
-
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.pending_range = None;
-        }
-        KeyCode::Char('j') | KeyCode::Down => {
-            app.category_selected = (app.category_selected + 1) % total;
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            app.category_selected = if app.category_selected == 0 {
-                total - 1
-            } else {
-                app.category_selected - 1
-            };
-        }
-        KeyCode::Enter => {
-            app.pending_category = if app.category_selected == 0 {
-                None
-            } else {
-                Some(Category::all()[app.category_selected - 1])
-            };
-            app.input_buffer.clear();
-            app.input_target = InputTarget::Comment;
-            app.mode = Mode::Input;
-        }
-        // Quick select
-        KeyCode::Char('0') => {
-            app.pending_category = None;
-            app.input_buffer.clear();
-            app.input_target = InputTarget::Comment;
-            app.mode = Mode::Input;
-        }
-        _ => {}
-    }
+/// Convert a crossterm key event to a keymap chord
+fn key_chord(key: &KeyEvent) -> Option<KeyChord> {
+    let code = match key.code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::F(n) => Key::F(n),
+        _ => return None,
+    };
+    Some(KeyChord {
+        key: code,
+        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+        alt: key.modifiers.contains(KeyModifiers::ALT),
+    })
 }
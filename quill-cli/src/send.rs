@@ -0,0 +1,256 @@
+//! Running the send command in the background while the TUI keeps drawing
+
+use std::io::{Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+/// How long to keep reading output after the command exits, in case
+/// something it started still holds the pipes open
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// How long to wait for the rest of stderr once output is done
+const STDERR_WAIT: Duration = Duration::from_millis(100);
+
+/// A shell command started by the send action
+pub struct Running {
+    child: Child,
+    stdout: Receiver<String>,
+    stderr: Receiver<String>,
+    stdout_closed: bool,
+    /// Exit status and when it was seen
+    exited: Option<(ExitStatus, Instant)>,
+}
+
+impl Running {
+    /// Start `command` in the shell, writing `input` to its stdin
+    pub fn spawn(command: &str, input: &str) -> Result<Self> {
+        let mut child = shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run {}", command))?;
+
+        let mut stdin = child.stdin.take().context("No stdin")?;
+        let input = input.to_string();
+        // A command that exits without reading its input is reported by its status
+        thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let stdout = child.stdout.take().context("No stdout")?;
+        let (stdout_tx, stdout_rx) = mpsc::channel();
+        thread::spawn(move || stream_utf8(stdout, |chunk| stdout_tx.send(chunk).is_ok()));
+
+        let mut stderr = child.stderr.take().context("No stderr")?;
+        let (stderr_tx, stderr_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text);
+            let _ = stderr_tx.send(text);
+        });
+
+        Ok(Self {
+            child,
+            stdout: stdout_rx,
+            stderr: stderr_rx,
+            stdout_closed: false,
+            exited: None,
+        })
+    }
+
+    /// Output received since the last poll, and once the command has exited,
+    /// `Some` with its error if it failed
+    pub fn poll(&mut self) -> (String, Option<Option<String>>) {
+        let mut output = String::new();
+        loop {
+            match self.stdout.try_recv() {
+                Ok(chunk) => output.push_str(&chunk),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.stdout_closed = true;
+                    break;
+                }
+            }
+        }
+
+        if self.exited.is_none() {
+            match self.child.try_wait() {
+                Ok(Some(status)) => {
+                    // Stop anything the command left running in the background
+                    kill_group(&self.child);
+                    self.exited = Some((status, Instant::now()));
+                }
+                Ok(None) => return (output, None),
+                Err(e) => return (output, Some(Some(e.to_string()))),
+            }
+        }
+        let Some((status, exited_at)) = self.exited else {
+            return (output, None);
+        };
+        if !self.stdout_closed && exited_at.elapsed() < OUTPUT_GRACE {
+            return (output, None);
+        }
+
+        let error = (!status.success()).then(|| {
+            let stderr = self.stderr.recv_timeout(STDERR_WAIT).unwrap_or_default();
+            match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+                Some(line) => format!("{} ({})", line.trim(), status),
+                None => status.to_string(),
+            }
+        });
+        (output, Some(error))
+    }
+
+    /// Stop the command and everything it started
+    pub fn kill(&mut self) {
+        kill_group(&self.child);
+        let _ = self.child.kill();
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if matches!(self.child.try_wait(), Ok(None)) {
+            self.kill();
+        }
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+/// The shell leads its own process group, so the commands it starts can be
+/// stopped together
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]).process_group(0);
+    shell
+}
+
+#[cfg(unix)]
+fn kill_group(child: &Child) {
+    // SAFETY: kill has no memory effects; a negative pid names the group
+    // the shell leads, and the group outlives the shell while it has members
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(_child: &Child) {}
+
+/// Read `reader` to the end, passing on text as it arrives without splitting
+/// characters, until `send` returns false
+fn stream_utf8(mut reader: impl Read, mut send: impl FnMut(String) -> bool) {
+    let mut buf = [0u8; 4096];
+    let mut pending = Vec::new();
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&buf[..n]);
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            // Hold back an incomplete character at the end, but not invalid bytes
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => pending.len(),
+        };
+        let chunk = String::from_utf8_lossy(&pending[..valid]).into_owned();
+        pending.drain(..valid);
+        if !chunk.is_empty() && !send(chunk) {
+            return;
+        }
+    }
+    if !pending.is_empty() {
+        send(String::from_utf8_lossy(&pending).into_owned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Poll a command until it finishes, returning its output and error
+    fn finish(mut running: Running) -> (String, Option<String>) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = String::new();
+        loop {
+            let (chunk, exit) = running.poll();
+            output.push_str(&chunk);
+            if let Some(error) = exit {
+                return (output, error);
+            }
+            assert!(Instant::now() < deadline, "command did not finish");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_stream_utf8_holds_back_split_characters() {
+        // Each read returns one side of the split "é"
+        let reader = (&b"caf\xc3"[..]).chain(&b"\xa9 ok"[..]);
+        let mut chunks = Vec::new();
+        stream_utf8(reader, |chunk| {
+            chunks.push(chunk);
+            true
+        });
+        assert_eq!(chunks, vec!["caf", "é ok"]);
+
+        let mut chunks = Vec::new();
+        stream_utf8(&b"bad\xff"[..], |chunk| {
+            chunks.push(chunk);
+            true
+        });
+        assert_eq!(chunks, vec!["bad\u{fffd}"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_running_streams_output() {
+        let running = Running::spawn("cat", "héllo\nwörld\n").unwrap();
+        assert_eq!(finish(running), ("héllo\nwörld\n".to_string(), None));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_running_reports_stderr_on_failure() {
+        let running = Running::spawn(
+            "printf 'partial'; printf 'first\\nbad key\\n' >&2; exit 3",
+            "",
+        )
+        .unwrap();
+        let (output, error) = finish(running);
+        assert_eq!(output, "partial");
+        let error = error.unwrap();
+        assert!(error.starts_with("bad key ("), "{}", error);
+        assert!(error.contains('3'), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_running_finishes_despite_background_children() {
+        // The shell exits while a background sleep still holds its pipes
+        let started = Instant::now();
+        let running = Running::spawn("sleep 30 & printf done", "").unwrap();
+        assert_eq!(finish(running), ("done".to_string(), None));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Cancelling stops the shell and what it started
+        let mut running = Running::spawn("sleep 30 & sleep 30", "").unwrap();
+        running.kill();
+        let (_, error) = finish(running);
+        assert!(error.is_some());
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
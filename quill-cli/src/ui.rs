@@ -4,25 +4,119 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
-use quill_core::{App, Category, Focus, InputTarget, Mode, Severity};
-
-// Catppuccin Mocha colors
-const SURFACE0: Color = Color::Rgb(49, 50, 68);
-const SURFACE1: Color = Color::Rgb(69, 71, 90);
-const TEXT: Color = Color::Rgb(205, 214, 244);
-const SUBTEXT0: Color = Color::Rgb(166, 173, 200);
-const RED: Color = Color::Rgb(243, 139, 168);
-const YELLOW: Color = Color::Rgb(249, 226, 175);
-const GREEN: Color = Color::Rgb(166, 227, 161);
-const BLUE: Color = Color::Rgb(137, 180, 250);
-const MAUVE: Color = Color::Rgb(203, 166, 247);
-const TEAL: Color = Color::Rgb(148, 226, 213);
-
-pub fn draw(frame: &mut Frame, app: &App) {
+use quill_core::{
+    general_label, layout, outline, App, Category, EditorLayout, Focus, GutterMode, InlineComments,
+    InputTarget, Mode, ProposalStatus, Scrollbar, Segment, Severity, SidebarRow, SortOrder, Theme,
+    ThemeColor,
+};
+
+/// Theme colors resolved for rendering
+struct Palette {
+    background: Color,
+    surface0: Color,
+    surface1: Color,
+    text: Color,
+    subtext0: Color,
+    red: Color,
+    yellow: Color,
+    green: Color,
+    blue: Color,
+    mauve: Color,
+    teal: Color,
+    monochrome: bool,
+}
+
+impl Palette {
+    fn new(theme: &Theme) -> Self {
+        Self {
+            background: color(theme.background),
+            surface0: color(theme.surface0),
+            surface1: color(theme.surface1),
+            text: color(theme.text),
+            subtext0: color(theme.subtext0),
+            red: color(theme.red),
+            yellow: color(theme.yellow),
+            green: color(theme.green),
+            blue: color(theme.blue),
+            mauve: color(theme.mauve),
+            teal: color(theme.teal),
+            monochrome: theme.is_monochrome(),
+        }
+    }
+
+    fn severity(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::MustFix => self.red,
+            Severity::ShouldFix => self.yellow,
+            Severity::Consider => self.green,
+        }
+    }
+
+    /// Style for selected items, using reversed text when the theme has no colors
+    fn highlight(&self) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().bg(self.surface1)
+        }
+    }
+
+    /// Style for search matches
+    fn search_match(&self) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(self.surface0).bg(self.yellow)
+        }
+    }
+}
+
+fn color(color: ThemeColor) -> Color {
+    match color {
+        ThemeColor::Reset => Color::Reset,
+        ThemeColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+        ThemeColor::Ansi(index) => match index {
+            0 => Color::Black,
+            1 => Color::Red,
+            2 => Color::Green,
+            3 => Color::Yellow,
+            4 => Color::Blue,
+            5 => Color::Magenta,
+            6 => Color::Cyan,
+            7 => Color::Gray,
+            8 => Color::DarkGray,
+            9 => Color::LightRed,
+            10 => Color::LightGreen,
+            11 => Color::LightYellow,
+            12 => Color::LightBlue,
+            13 => Color::LightMagenta,
+            14 => Color::LightCyan,
+            15 => Color::White,
+            _ => Color::Indexed(index),
+        },
+    }
+}
+
+/// Screen regions from the last draw, used to route mouse events
+#[derive(Debug, Clone, Default)]
+pub struct ScreenMap {
+    /// Editor text area, excluding gutter and scrollbar
+    pub editor: Rect,
+    pub editor_scroll: usize,
+    pub layout: EditorLayout,
+    /// Sidebar list area, excluding borders
+    pub sidebar: Rect,
+    /// Visible annotation index shown on each sidebar row
+    pub sidebar_rows: Vec<Option<usize>>,
+}
+
+pub fn draw(frame: &mut Frame, app: &App) -> ScreenMap {
+    let mut screen = ScreenMap::default();
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -32,8 +126,22 @@ pub fn draw(frame: &mut Frame, app: &App) {
         ])
         .split(frame.area());
 
+    let colors = Palette::new(&app.theme);
+    frame.render_widget(
+        Block::default().style(Style::default().fg(colors.text).bg(colors.background)),
+        frame.area(),
+    );
+
     draw_title_bar(frame, app, chunks[0]);
-    draw_main_area(frame, app, chunks[1]);
+    if app.mode == Mode::Preview {
+        draw_preview(frame, app, chunks[1]);
+    } else if app.mode == Mode::Output {
+        draw_output(frame, app, chunks[1]);
+    } else if app.mode == Mode::Diff {
+        draw_diff(frame, app, chunks[1]);
+    } else {
+        draw_main_area(frame, app, chunks[1], &mut screen);
+    }
     draw_status_bar(frame, app, chunks[2]);
 
     // Draw popups/overlays
@@ -41,18 +149,20 @@ pub fn draw(frame: &mut Frame, app: &App) {
         Mode::SeverityPicker => draw_severity_picker(frame, app),
         Mode::CategoryPicker => draw_category_picker(frame, app),
         Mode::Input => draw_input_dialog(frame, app),
-        Mode::Help => draw_help(frame),
+        Mode::Help => draw_help(frame, app),
+        Mode::Detail => draw_detail(frame, app),
+        Mode::Chooser => draw_chooser(frame, app),
+        Mode::Review => draw_review(frame, app),
         _ => {}
     }
+
+    screen
 }
 
 fn draw_title_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
     let title = app.title();
-    let ann_count = app
-        .document
-        .as_ref()
-        .map(|d| d.annotations.len())
-        .unwrap_or(0);
+    let ann_count = app.visible_annotations().len();
 
     let current = if ann_count > 0 {
         app.sidebar_selected + 1
@@ -64,31 +174,109 @@ fn draw_title_bar(frame: &mut Frame, app: &App, area: Rect) {
         " Quill TUI - {} [{}/{}]",
         title, current, ann_count
     );
+    let title_text = if app.filter.is_active() {
+        format!("{} filter: {}", title_text, app.filter.describe())
+    } else {
+        title_text
+    };
 
-    let title_bar = Paragraph::new(title_text)
-        .style(Style::default().fg(TEXT).bg(SURFACE0));
+    let title_bar =
+        Paragraph::new(title_text).style(Style::default().fg(colors.text).bg(colors.surface0));
 
     frame.render_widget(title_bar, area);
 }
 
-fn draw_main_area(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_main_area(frame: &mut Frame, app: &App, area: Rect, screen: &mut ScreenMap) {
+    let outline_width = if app.outline_visible { 28 } else { 0 };
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Min(0),        // Editor
-            Constraint::Length(30),    // Sidebar
+            Constraint::Length(outline_width), // Outline
+            Constraint::Min(0),                // Editor
+            Constraint::Length(30),            // Sidebar
         ])
         .split(area);
 
-    draw_editor(frame, app, chunks[0]);
-    draw_sidebar(frame, app, chunks[1]);
+    if app.outline_visible {
+        draw_outline(frame, app, chunks[0]);
+    }
+    draw_editor(frame, app, chunks[1], screen);
+    draw_sidebar(frame, app, chunks[2], screen);
+}
+
+fn draw_outline(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
+    let outline_style = if app.focus == Focus::Outline {
+        Style::default().fg(colors.blue)
+    } else {
+        Style::default().fg(colors.subtext0)
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(outline_style)
+        .title("Outline");
+
+    let doc = match &app.document {
+        Some(d) => d,
+        None => {
+            frame.render_widget(block, area);
+            return;
+        }
+    };
+
+    let items: Vec<ListItem> = app
+        .headings()
+        .iter()
+        .enumerate()
+        .map(|(i, heading)| {
+            let selected = i == app.outline_selected;
+            let marker = if selected { ">" } else { " " };
+            let indent = "  ".repeat(heading.level.saturating_sub(1));
+
+            let style = if selected {
+                colors.highlight().fg(colors.text)
+            } else if heading.level == 1 {
+                Style::default()
+                    .fg(colors.text)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(colors.text)
+            };
+
+            let mut spans = vec![Span::styled(
+                format!("{}{}{}", marker, indent, heading.title),
+                style,
+            )];
+
+            // Unresolved annotation counts for this section
+            let counts = outline::section_counts(doc, heading);
+            for severity in Severity::all() {
+                let count = counts.get(*severity);
+                if count > 0 {
+                    spans.push(Span::styled(
+                        format!(" {}", count),
+                        Style::default().fg(colors.severity(*severity)),
+                    ));
+                }
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    // Keep the selected heading scrolled into view
+    let mut state = ListState::default().with_selected(Some(app.outline_selected));
+    let list = List::new(items).block(block);
+    frame.render_stateful_widget(list, area, &mut state);
 }
 
-fn draw_editor(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_editor(frame: &mut Frame, app: &App, area: Rect, screen: &mut ScreenMap) {
+    let colors = Palette::new(&app.theme);
     let editor_style = if app.focus == Focus::Editor {
-        Style::default().fg(BLUE)
+        Style::default().fg(colors.blue)
     } else {
-        Style::default().fg(SUBTEXT0)
+        Style::default().fg(colors.subtext0)
     };
 
     let mode_indicator = match app.mode {
@@ -107,80 +295,298 @@ fn draw_editor(frame: &mut Frame, app: &App, area: Rect) {
     // Render document content with annotations highlighted
     if let Some(doc) = &app.document {
         let content = &doc.content;
-        let annotations = doc.annotations_sorted();
-
-        // Build styled lines
-        let mut lines: Vec<Line> = Vec::new();
-        let mut current_line_spans: Vec<Span> = Vec::new();
-        let mut char_idx = 0;
+        let coverage = doc.coverage();
+        let mut coverage_idx = 0;
 
         // Get selection range if in visual mode
         let selection = app.get_selection_range();
 
-        for (_line_idx, line_text) in content.lines().enumerate() {
-            current_line_spans.clear();
+        // Inline comments, keyed by the line they follow
+        let anchors = app.inline_comment_anchors();
+        // Suggested replacements, struck through in the text and shown below it
+        let suggestions = app.suggestion_anchors();
+        // Insertion points, shown as a caret before the character they precede
+        let points = doc.insertion_points();
+
+        // Gutter: line number, severity marker, space
+        let markers = app.line_markers();
+        let number_width = app.cursor.line_count().max(1).to_string().len();
+        let gutter_width = if app.gutter == GutterMode::Off {
+            0
+        } else {
+            number_width + 2
+        };
+
+        // Text wraps within the area left of the scrollbar and right of the gutter
+        let text_area = Rect {
+            width: inner.width.saturating_sub(1),
+            ..inner
+        };
+        let bar_area = Rect {
+            x: inner.x + text_area.width,
+            width: inner.width - text_area.width,
+            ..inner
+        };
+        let mut layout = EditorLayout::new((text_area.width as usize).saturating_sub(gutter_width));
+        let mut lines: Vec<Line> = Vec::new();
+        let mut char_idx = 0;
+
+        for (line_idx, line_text) in content.lines().enumerate() {
             let line_start = char_idx;
+            let mut cells: Vec<(char, Style)> = Vec::new();
 
             let mut col = 0;
-            for ch in line_text.chars() {
+            let mut max_overlap = 0;
+            for (char_col, ch) in line_text.chars().enumerate() {
                 let offset = line_start + col;
 
                 // Determine styling for this character
-                let mut style = Style::default().fg(TEXT);
+                let mut style = Style::default().fg(colors.text);
 
                 // Check if in selection
                 if let Some((sel_start, sel_end)) = selection {
                     if offset >= sel_start && offset < sel_end {
-                        style = style.bg(SURFACE1).add_modifier(Modifier::BOLD);
+                        style = style.patch(colors.highlight()).add_modifier(Modifier::BOLD);
                     }
                 }
 
-                // Check if in an annotation
-                for ann in &annotations {
-                    if ann.range.contains(offset) {
-                        let color = severity_color(ann.severity);
-                        style = style.fg(color).add_modifier(Modifier::UNDERLINED);
-                        break;
+                // Check if in annotations; overlaps take the most severe color
+                while coverage
+                    .get(coverage_idx)
+                    .is_some_and(|c| c.end_offset <= offset)
+                {
+                    coverage_idx += 1;
+                }
+                if let Some(stretch) = coverage
+                    .get(coverage_idx)
+                    .filter(|c| c.start_offset <= offset)
+                {
+                    let color = colors.severity(stretch.severity);
+                    style = style.fg(color).add_modifier(Modifier::UNDERLINED);
+                    if stretch.count > 1 {
+                        style = style.add_modifier(Modifier::BOLD).bg(colors.surface0);
                     }
+                    max_overlap = max_overlap.max(stretch.count);
+                }
+                if suggestions
+                    .iter()
+                    .any(|(_, a)| a.range.start_offset <= offset && offset < a.range.end_offset)
+                {
+                    style = style.add_modifier(Modifier::CROSSED_OUT);
+                }
+                if let Some(point) = points.iter().find(|a| a.range.start_offset == offset) {
+                    cells.push(('‸', caret_style(&colors, point.severity)));
+                    layout.insert_cell(line_idx, char_col);
                 }
 
-                current_line_spans.push(Span::styled(ch.to_string(), style));
+                cells.push((ch, style));
                 col += ch.len_utf8();
             }
 
-            lines.push(Line::from(current_line_spans.clone()));
+            // Points at the end of the line get a caret after the text
+            let line_end = line_start + line_text.len();
+            if let Some(point) = points.iter().find(|a| a.range.start_offset == line_end) {
+                cells.push(('‸', caret_style(&colors, point.severity)));
+                layout.insert_cell(line_idx, line_text.chars().count());
+            }
+
+            // Mark lines with overlapping annotations
+            if max_overlap > 1 {
+                let style = Style::default()
+                    .fg(colors.subtext0)
+                    .add_modifier(Modifier::DIM);
+                cells.extend(format!(" [{}]", max_overlap).chars().map(|c| (c, style)));
+            }
+
+            let line_comments: Vec<_> = anchors
+                .iter()
+                .filter(|(line, _)| *line == line_idx)
+                .map(|(_, ann)| *ann)
+                .collect();
+
+            if app.inline_comments == InlineComments::EndOfLine {
+                for ann in &line_comments {
+                    let style = Style::default()
+                        .fg(colors.severity(ann.severity))
+                        .add_modifier(Modifier::DIM | Modifier::ITALIC);
+                    let text = format!("  <- {}", ann.comment.replace('\n', " "));
+                    cells.extend(text.chars().map(|c| (c, style)));
+                }
+            }
+
+            let gutter = match app.line_number_label(line_idx) {
+                Some(number) => {
+                    let number_style = if line_idx == app.cursor_pos().0 {
+                        Style::default().fg(colors.text)
+                    } else {
+                        Style::default().fg(colors.subtext0)
+                    };
+                    vec![
+                        Span::styled(
+                            format!("{:>width$}", number, width = number_width),
+                            number_style,
+                        ),
+                        match markers.get(line_idx).copied().flatten() {
+                            Some(severity) => {
+                                Span::styled("*", Style::default().fg(colors.severity(severity)))
+                            }
+                            None => Span::raw(" "),
+                        },
+                        Span::raw(" "),
+                    ]
+                }
+                None => Vec::new(),
+            };
+            push_wrapped(
+                &mut lines,
+                &mut layout,
+                Some(line_idx),
+                gutter,
+                &cells,
+                gutter_width,
+            );
+
+            for (_, ann) in suggestions.iter().filter(|(line, _)| *line == line_idx) {
+                let rows: Vec<(String, Style)> = match ann.suggestion.as_deref() {
+                    Some("") | None => vec![(
+                        "  - (delete)".to_string(),
+                        Style::default().fg(colors.red).add_modifier(Modifier::DIM),
+                    )],
+                    Some(text) => text
+                        .lines()
+                        .map(|l| (format!("  + {}", l), Style::default().fg(colors.green)))
+                        .collect(),
+                };
+                for (text, style) in rows {
+                    let cells: Vec<_> = text.chars().map(|c| (c, style)).collect();
+                    push_wrapped(
+                        &mut lines,
+                        &mut layout,
+                        None,
+                        Vec::new(),
+                        &cells,
+                        gutter_width,
+                    );
+                }
+            }
+
+            if app.inline_comments == InlineComments::Below {
+                for ann in &line_comments {
+                    let style = Style::default()
+                        .fg(colors.severity(ann.severity))
+                        .add_modifier(Modifier::DIM | Modifier::ITALIC);
+                    for comment_line in ann.comment.lines() {
+                        let text = format!("  | {}", comment_line);
+                        let cells: Vec<_> = text.chars().map(|c| (c, style)).collect();
+                        push_wrapped(
+                            &mut lines,
+                            &mut layout,
+                            None,
+                            Vec::new(),
+                            &cells,
+                            gutter_width,
+                        );
+                    }
+                }
+            }
             char_idx = line_start + line_text.len() + 1; // +1 for newline
         }
 
-        // Calculate scroll offset based on cursor
+        // Calculate scroll offset based on cursor's screen row
         let cursor = app.cursor_pos();
+        let (cursor_row, cursor_col) = layout.cursor_position(cursor.0, cursor.1).unwrap_or(cursor);
         let visible_height = inner.height as usize;
-        let scroll_offset = if cursor.0 >= visible_height {
-            cursor.0 - visible_height + 1
-        } else {
-            0
-        };
+        let scroll_offset = layout::scroll_offset(cursor_row, visible_height);
 
-        let paragraph = Paragraph::new(lines)
-            .scroll((scroll_offset as u16, 0))
-            .wrap(Wrap { trim: false });
+        // Scrollbar with annotation ticks in the rightmost column
+        let mut scrollbar = Scrollbar::new(layout.rows.len(), scroll_offset, visible_height);
+        for (line_idx, marker) in markers.iter().enumerate() {
+            if let (Some(severity), Some(row)) = (marker, layout.first_row(line_idx)) {
+                scrollbar.mark(row, *severity);
+            }
+        }
+        draw_scrollbar(frame, &scrollbar, &colors, bar_area);
 
-        frame.render_widget(paragraph, inner);
+        let paragraph = Paragraph::new(lines).scroll((scroll_offset as u16, 0));
+        frame.render_widget(paragraph, text_area);
 
         // Draw cursor
-        let cursor_y = inner.y + (cursor.0 - scroll_offset) as u16;
-        let cursor_x = inner.x + cursor.1 as u16;
-        if cursor_y < inner.y + inner.height && cursor_x < inner.x + inner.width {
+        let cursor_y = inner.y + (cursor_row - scroll_offset) as u16;
+        let cursor_x = inner.x + (gutter_width + cursor_col) as u16;
+        if cursor_y < inner.y + inner.height && cursor_x < text_area.x + text_area.width {
             frame.set_cursor_position((cursor_x, cursor_y));
         }
+
+        screen.editor = Rect {
+            x: text_area.x + gutter_width as u16,
+            width: text_area.width.saturating_sub(gutter_width as u16),
+            ..text_area
+        };
+        screen.editor_scroll = scroll_offset;
+        screen.layout = layout;
+    }
+}
+
+fn draw_scrollbar(frame: &mut Frame, scrollbar: &Scrollbar, colors: &Palette, area: Rect) {
+    let rows: Vec<Line> = scrollbar
+        .ticks
+        .iter()
+        .enumerate()
+        .map(|(row, tick)| {
+            let style = if scrollbar.in_thumb(row) {
+                colors.highlight()
+            } else {
+                Style::default().bg(colors.surface0)
+            };
+            match tick {
+                Some(severity) => Span::styled("━", style.fg(colors.severity(*severity))),
+                None => Span::styled(" ", style),
+            }
+            .into()
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(rows), area);
+}
+
+/// Style of the caret drawn at an insertion point
+fn caret_style(colors: &Palette, severity: Severity) -> Style {
+    Style::default()
+        .fg(colors.severity(severity))
+        .add_modifier(Modifier::BOLD)
+}
+
+/// Append a logical row of styled characters, wrapped to the layout width.
+/// The gutter is drawn on the first screen row and left blank on the rest.
+fn push_wrapped<'a>(
+    lines: &mut Vec<Line<'a>>,
+    layout: &mut EditorLayout,
+    line: Option<usize>,
+    gutter: Vec<Span<'a>>,
+    cells: &[(char, Style)],
+    gutter_width: usize,
+) {
+    let mut gutter = Some(gutter);
+    for row in layout.push(line, cells.len()) {
+        let display = layout.rows[row];
+        let mut spans = match gutter.take() {
+            Some(g) if !g.is_empty() => g,
+            _ => vec![Span::raw(" ".repeat(gutter_width))],
+        };
+        spans.extend(
+            cells[display.start_col..display.start_col + display.len]
+                .iter()
+                .map(|(ch, style)| Span::styled(ch.to_string(), *style)),
+        );
+        lines.push(Line::from(spans));
     }
 }
 
-fn draw_sidebar(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_sidebar(frame: &mut Frame, app: &App, area: Rect, screen: &mut ScreenMap) {
+    let colors = Palette::new(&app.theme);
     let sidebar_style = if app.focus == Focus::Sidebar {
-        Style::default().fg(BLUE)
+        Style::default().fg(colors.blue)
     } else {
-        Style::default().fg(SUBTEXT0)
+        Style::default().fg(colors.subtext0)
     };
 
     let ann_count = app
@@ -188,62 +594,119 @@ fn draw_sidebar(frame: &mut Frame, app: &App, area: Rect) {
         .as_ref()
         .map(|d| d.annotations.len())
         .unwrap_or(0);
+    let rows = app.sidebar_rows();
+
+    let mut title = if app.filter.is_active() {
+        format!(
+            "Annotations ({}/{})",
+            app.filtered_annotations().len(),
+            ann_count
+        )
+    } else {
+        format!("Annotations ({})", ann_count)
+    };
+    if app.sort_order != SortOrder::Position {
+        title.push_str(&format!(" by {}", app.sort_order.as_str()));
+    }
 
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(sidebar_style)
-        .title(format!("Annotations ({})", ann_count));
+        .title(title);
 
-    if let Some(doc) = &app.document {
-        let items: Vec<ListItem> = doc
-            .annotations_sorted()
+    if app.document.is_some() {
+        let mut selected_row = None;
+        let items: Vec<ListItem> = rows
             .iter()
             .enumerate()
-            .map(|(i, ann)| {
-                let selected = i == app.sidebar_selected;
-                let marker = if selected { ">" } else { " " };
-                let resolved = if ann.is_resolved { "~" } else { "" };
-
-                let severity_str = ann.severity.short();
-                let text_preview: String = ann
-                    .selected_text
-                    .chars()
-                    .take(15)
-                    .collect::<String>()
-                    .replace('\n', " ");
-
-                let line1 = format!(
-                    "{} [{}]{} \"{}...\"",
-                    marker, severity_str, resolved, text_preview
-                );
-                let line2 = format!(
-                    "   {}",
-                    ann.comment.chars().take(20).collect::<String>()
-                );
-
-                let style = if selected {
-                    Style::default().fg(TEXT).bg(SURFACE1)
-                } else if ann.is_resolved {
-                    Style::default().fg(SUBTEXT0)
-                } else {
-                    Style::default().fg(TEXT)
-                };
-
-                ListItem::new(vec![
-                    Line::from(Span::styled(line1, style)),
-                    Line::from(Span::styled(line2, style.fg(SUBTEXT0))),
-                ])
+            .map(|(row_idx, row)| match row {
+                SidebarRow::Header {
+                    label,
+                    count,
+                    collapsed,
+                } => {
+                    let arrow = if *collapsed { "+" } else { "-" };
+                    ListItem::new(Line::from(Span::styled(
+                        format!("{} {} ({})", arrow, label, count),
+                        Style::default()
+                            .fg(colors.mauve)
+                            .add_modifier(Modifier::BOLD),
+                    )))
+                }
+                SidebarRow::Item {
+                    index,
+                    annotation: ann,
+                } => {
+                    let selected = *index == app.sidebar_selected;
+                    if selected {
+                        selected_row = Some(row_idx);
+                    }
+                    let marker = if selected { ">" } else { " " };
+                    let resolved = if ann.is_resolved { "~" } else { "" };
+
+                    let severity_str = ann.severity.short();
+                    let text_preview: String = ann
+                        .selected_text
+                        .chars()
+                        .take(15)
+                        .collect::<String>()
+                        .replace('\n', " ");
+
+                    let line1 =
+                        if let Some(doc) = app.document.as_ref().filter(|_| ann.is_general()) {
+                            let label: String = general_label(doc, ann).chars().take(24).collect();
+                            format!("{} [{}]{} {}", marker, severity_str, resolved, label)
+                        } else if ann.range.is_point() {
+                            format!("{} [{}]{} ‸ insert", marker, severity_str, resolved)
+                        } else {
+                            format!(
+                                "{} [{}]{} \"{}...\"",
+                                marker, severity_str, resolved, text_preview
+                            )
+                        };
+                    let line2 = format!("   {}", ann.comment.chars().take(20).collect::<String>());
+
+                    let style = if selected {
+                        colors.highlight().fg(colors.text)
+                    } else if ann.is_resolved {
+                        Style::default().fg(colors.subtext0)
+                    } else {
+                        Style::default().fg(colors.text)
+                    };
+
+                    ListItem::new(vec![
+                        Line::from(Span::styled(line1, style)),
+                        Line::from(Span::styled(line2, style.fg(colors.subtext0))),
+                    ])
+                }
             })
             .collect();
 
-        let list = List::new(items).block(block);
-        frame.render_widget(list, area);
+        // Keep the selected annotation scrolled into view
+        let mut state = ListState::default().with_selected(selected_row);
+        let list = List::new(items).block(block.clone());
+        frame.render_stateful_widget(list, area, &mut state);
+
+        // Record which annotation each screen row shows
+        screen.sidebar = block.inner(area);
+        for row in rows.iter().skip(state.offset()) {
+            match row {
+                SidebarRow::Header { .. } => screen.sidebar_rows.push(None),
+                SidebarRow::Item { index, .. } => {
+                    screen.sidebar_rows.extend([Some(*index), Some(*index)]);
+                }
+            }
+            if screen.sidebar_rows.len() >= screen.sidebar.height as usize {
+                break;
+            }
+        }
     } else {
         frame.render_widget(block, area);
     }
 }
 
 fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
     let mode_str = match app.mode {
         Mode::Normal => "NORMAL",
         Mode::Visual => "VISUAL",
@@ -251,6 +714,12 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         Mode::CategoryPicker => "CATEGORY",
         Mode::SeverityPicker => "SEVERITY",
         Mode::Help => "HELP",
+        Mode::Detail => "DETAIL",
+        Mode::Chooser => "CHOOSE",
+        Mode::Preview => "PREVIEW",
+        Mode::Review => "REVIEW",
+        Mode::Output => "OUTPUT",
+        Mode::Diff => "DIFF",
     };
 
     let status = app
@@ -267,19 +736,337 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         ""
     );
 
-    let status_bar = Paragraph::new(status_text)
-        .style(Style::default().fg(SUBTEXT0).bg(SURFACE0));
+    let status_bar =
+        Paragraph::new(status_text).style(Style::default().fg(colors.subtext0).bg(colors.surface0));
 
     frame.render_widget(status_bar, area);
 }
 
+fn draw_preview(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
+    let preview = &app.preview;
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.blue))
+        .title(format!(
+            " Prompt Preview ({}) - {} chars, ~{} tokens{}{}{} ",
+            app.template.name,
+            preview.char_count(),
+            preview.token_estimate(),
+            if preview.parts > 1 {
+                format!(", {} prompts", preview.parts)
+            } else {
+                String::new()
+            },
+            if app.export_scoped { ", filtered" } else { "" },
+            preview
+                .over_budget
+                .as_ref()
+                .map(|warning| format!(", {}", warning))
+                .unwrap_or_default()
+        ))
+        .title_bottom(
+            " / search  n/N match  y copy  w save  f filter  t full text  s structured  x excerpts  T template  r regenerate  Esc close ",
+        );
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let (text_area, search_area) = if preview.searching {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(inner);
+        (chunks[0], Some(chunks[1]))
+    } else {
+        (inner, None)
+    };
+
+    let lines: Vec<Line> = preview
+        .text
+        .lines()
+        .skip(preview.scroll)
+        .take(text_area.height as usize)
+        .map(|line| preview_line(line, &preview.highlights(line), &colors))
+        .collect();
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), text_area);
+
+    if let Some(search_area) = search_area {
+        let search = Paragraph::new(format!("/{}", preview.query))
+            .style(Style::default().fg(colors.text).bg(colors.surface0));
+        frame.render_widget(search, search_area);
+    }
+}
+
+/// Style one line of a Markdown prompt, highlighting search matches
+fn preview_line(
+    line: &str,
+    highlights: &[std::ops::Range<usize>],
+    colors: &Palette,
+) -> Line<'static> {
+    let text = Style::default().fg(colors.text);
+    let label = Style::default().fg(colors.subtext0);
+
+    // Base styles as (start byte, style) runs
+    let runs: Vec<(usize, Style)> = if line.starts_with('#') {
+        let level = line.chars().take_while(|&c| c == '#').count();
+        let color = if level <= 2 {
+            colors.mauve
+        } else {
+            colors.blue
+        };
+        vec![(0, Style::default().fg(color).add_modifier(Modifier::BOLD))]
+    } else if line == "---" {
+        vec![(0, label)]
+    } else if line.starts_with("**") && line.ends_with("**") {
+        vec![(0, text.add_modifier(Modifier::BOLD))]
+    } else if let Some(colon) = line.strip_prefix("- ").and_then(|rest| rest.find(": ")) {
+        vec![(0, Style::default().fg(colors.teal)), (colon + 4, text)]
+    } else {
+        vec![(0, text)]
+    };
+
+    let mut cuts: Vec<usize> = runs.iter().map(|(start, _)| *start).collect();
+    cuts.extend(highlights.iter().flat_map(|r| [r.start, r.end]));
+    cuts.push(line.len());
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let spans: Vec<Span> = cuts
+        .windows(2)
+        .map(|w| {
+            let style = runs
+                .iter()
+                .rev()
+                .find(|(start, _)| *start <= w[0])
+                .map(|(_, style)| *style)
+                .unwrap_or(text);
+            let style = if highlights.iter().any(|r| r.contains(&w[0])) {
+                style.patch(colors.search_match())
+            } else {
+                style
+            };
+            Span::styled(line[w[0]..w[1]].to_string(), style)
+        })
+        .collect();
+    Line::from(spans)
+}
+
+fn draw_output(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
+    let output = &app.output;
+
+    let (state, color) = if output.running {
+        ("running...".to_string(), colors.yellow)
+    } else if let Some(error) = &output.error {
+        (format!("failed: {}", error), colors.red)
+    } else {
+        ("done".to_string(), colors.green)
+    };
+    let hints = if output.running {
+        " j/k scroll  G follow  y copy  c cancel  Esc close "
+    } else {
+        " j/k scroll  y copy  R review revisions  D diff  Esc close "
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color))
+        .title(format!(" {} - {} ", output.command, state))
+        .title_bottom(hints);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let first = if output.follow {
+        output.line_count().saturating_sub(inner.height as usize)
+    } else {
+        output.scroll
+    };
+    let lines: Vec<Line> = output
+        .text
+        .lines()
+        .skip(first)
+        .take(inner.height as usize)
+        .map(|line| {
+            Line::from(Span::styled(
+                line.to_string(),
+                Style::default().fg(colors.text),
+            ))
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+fn draw_diff(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
+    let revision = &app.revision;
+    let Some(doc) = &app.document else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(34)])
+        .split(area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.blue))
+        .title(format!(
+            " Diff: {} - {} hunks, {} accepted, {} rejected ",
+            revision.name,
+            revision.hunks.len(),
+            revision.count(ProposalStatus::Accepted),
+            revision.count(ProposalStatus::Rejected)
+        ))
+        .title_bottom(
+            " j/k hunk  a/x accept/reject  u undo  A/X all  w write merged  Esc discard ",
+        );
+    let inner = block.inner(chunks[0]);
+    frame.render_widget(block, chunks[0]);
+
+    let text = Style::default().fg(colors.text);
+    let dim = Style::default()
+        .fg(colors.subtext0)
+        .add_modifier(Modifier::CROSSED_OUT);
+    let hunk_style = |index: usize, inserted: bool| {
+        let hunk = &revision.hunks[index];
+        let style = match (hunk.status, inserted) {
+            (ProposalStatus::Pending, false) => Style::default()
+                .fg(colors.red)
+                .add_modifier(Modifier::CROSSED_OUT),
+            (ProposalStatus::Pending, true) => Style::default()
+                .fg(colors.green)
+                .add_modifier(Modifier::UNDERLINED),
+            (ProposalStatus::Accepted, false) | (ProposalStatus::Rejected, true) => dim,
+            (ProposalStatus::Accepted, true) => Style::default()
+                .fg(colors.green)
+                .add_modifier(Modifier::BOLD),
+            (ProposalStatus::Rejected, false) => text,
+        };
+        if index == revision.selected {
+            style.patch(colors.highlight())
+        } else {
+            style
+        }
+    };
+    // Severity tags for the annotations a hunk touches, shown after it
+    let tags = |index: usize| {
+        revision.hunks[index]
+            .annotations
+            .iter()
+            .filter_map(|id| doc.annotations.iter().find(|a| a.id == *id))
+            .map(|ann| {
+                (
+                    format!(" [{}]", ann.severity.short()),
+                    Style::default().fg(colors.severity(ann.severity)),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut parts: Vec<(String, Style)> = Vec::new();
+    let mut current: Option<usize> = None;
+    for segment in revision.segments(&doc.content) {
+        let (piece, hunk, style) = match segment {
+            Segment::Equal(piece) => (piece, None, text),
+            Segment::Removed(piece, i) => (piece, Some(i), hunk_style(i, false)),
+            Segment::Inserted(piece, i) => (piece, Some(i), hunk_style(i, true)),
+        };
+        if let Some(prev) = current.filter(|&prev| Some(prev) != hunk) {
+            parts.extend(tags(prev));
+        }
+        current = hunk;
+        parts.push((piece.to_string(), style));
+    }
+    if let Some(prev) = current {
+        parts.extend(tags(prev));
+    }
+
+    let first = revision
+        .hunk_line(&doc.content, revision.selected)
+        .saturating_sub(inner.height as usize / 3);
+    let lines: Vec<Line> = multiline_spans(&parts).into_iter().skip(first).collect();
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+
+    draw_hunk_panel(frame, app, chunks[1]);
+}
+
+/// Status of the selected hunk and the annotations it touches
+fn draw_hunk_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let colors = Palette::new(&app.theme);
+    let revision = &app.revision;
+    let (Some(doc), Some(hunk)) = (&app.document, revision.hunks.get(revision.selected)) else {
+        return;
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.surface1))
+        .title(format!(
+            " Hunk {}/{} ",
+            revision.selected + 1,
+            revision.hunks.len()
+        ));
+
+    let label = Style::default().fg(colors.subtext0);
+    let status_color = match hunk.status {
+        ProposalStatus::Pending => colors.yellow,
+        ProposalStatus::Accepted => colors.green,
+        ProposalStatus::Rejected => colors.red,
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Status: ", label),
+            Span::styled(hunk.status.as_str(), Style::default().fg(status_color)),
+        ]),
+        Line::from(vec![
+            Span::styled("Line ", label),
+            Span::styled(
+                doc.line_number(hunk.original.start).to_string(),
+                Style::default().fg(colors.text),
+            ),
+        ]),
+        Line::from(""),
+    ];
+    if hunk.annotations.is_empty() {
+        lines.push(Line::from(Span::styled("No annotations", label)));
+    }
+    for ann in hunk
+        .annotations
+        .iter()
+        .filter_map(|id| doc.annotations.iter().find(|a| a.id == *id))
+    {
+        let color = colors.severity(ann.severity);
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("[{}] ", ann.severity.short()),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("\"{}\"", ann.selected_text), label),
+        ]));
+        lines.extend(ann.comment.lines().map(|l| {
+            Line::from(Span::styled(
+                l.to_string(),
+                Style::default().fg(colors.text),
+            ))
+        }));
+        lines.push(Line::from(""));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
+
 fn draw_severity_picker(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
     let area = centered_rect(40, 10, frame.area());
-    frame.render_widget(Clear, area);
+    clear_area(frame, &colors, area);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(MAUVE))
+        .border_style(Style::default().fg(colors.mauve))
         .title("Select Severity");
 
     let items: Vec<ListItem> = Severity::all()
@@ -288,9 +1075,9 @@ fn draw_severity_picker(frame: &mut Frame, app: &App) {
         .map(|(i, sev)| {
             let selected = i == app.severity_selected;
             let marker = if selected { ">" } else { " " };
-            let color = severity_color(*sev);
+            let color = colors.severity(*sev);
             let style = if selected {
-                Style::default().fg(color).bg(SURFACE1)
+                colors.highlight().fg(color)
             } else {
                 Style::default().fg(color)
             };
@@ -303,35 +1090,34 @@ fn draw_severity_picker(frame: &mut Frame, app: &App) {
 }
 
 fn draw_category_picker(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
     let area = centered_rect(40, 12, frame.area());
-    frame.render_widget(Clear, area);
+    clear_area(frame, &colors, area);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(MAUVE))
+        .border_style(Style::default().fg(colors.mauve))
         .title("Select Category (optional)");
 
-    let mut items: Vec<ListItem> = vec![
-        ListItem::new(if app.category_selected == 0 {
-            "> None"
-        } else {
-            "  None"
-        })
-        .style(if app.category_selected == 0 {
-            Style::default().fg(TEXT).bg(SURFACE1)
-        } else {
-            Style::default().fg(SUBTEXT0)
-        }),
-    ];
+    let mut items: Vec<ListItem> = vec![ListItem::new(if app.category_selected == 0 {
+        "> None"
+    } else {
+        "  None"
+    })
+    .style(if app.category_selected == 0 {
+        colors.highlight().fg(colors.text)
+    } else {
+        Style::default().fg(colors.subtext0)
+    })];
 
     for (i, cat) in Category::all().iter().enumerate() {
         let idx = i + 1;
         let selected = idx == app.category_selected;
         let marker = if selected { ">" } else { " " };
         let style = if selected {
-            Style::default().fg(TEAL).bg(SURFACE1)
+            colors.highlight().fg(colors.teal)
         } else {
-            Style::default().fg(TEAL)
+            Style::default().fg(colors.teal)
         };
         items.push(ListItem::new(format!("{} {}", marker, cat.as_str())).style(style));
     }
@@ -341,67 +1127,367 @@ fn draw_category_picker(frame: &mut Frame, app: &App) {
 }
 
 fn draw_input_dialog(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
     let area = centered_rect(60, 5, frame.area());
-    frame.render_widget(Clear, area);
+    clear_area(frame, &colors, area);
 
     let title = match app.input_target {
         InputTarget::Comment => "Enter comment",
+        InputTarget::Suggestion => "Suggested replacement (empty to delete)",
         InputTarget::FilePath => "Enter file path",
+        InputTarget::ResponsePath => "Enter response file path",
+        InputTarget::RevisionPath => "Enter revised file path",
+        InputTarget::Search => "Search comments (empty to clear)",
+        InputTarget::Command => "Command (Tab to complete)",
     };
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(GREEN))
+        .border_style(Style::default().fg(colors.green))
         .title(title);
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    let input = Paragraph::new(format!("{}_", app.input_buffer))
-        .style(Style::default().fg(TEXT));
+    let prompt = if app.input_target == InputTarget::Command {
+        ":"
+    } else {
+        ""
+    };
+    let input = Paragraph::new(format!("{}{}_", prompt, app.input_buffer))
+        .style(Style::default().fg(colors.text));
     frame.render_widget(input, inner);
 }
 
-fn draw_help(frame: &mut Frame) {
-    let area = centered_rect(60, 20, frame.area());
-    frame.render_widget(Clear, area);
+fn draw_chooser(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
+    let annotations = app.annotations_at_cursor();
+    let area = centered_rect(50, annotations.len() as u16 + 2, frame.area());
+    clear_area(frame, &colors, area);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(BLUE))
-        .title("Help");
+        .border_style(Style::default().fg(colors.mauve))
+        .title("Which annotation? (1-9 or j/k)");
 
-    let help_text = vec![
-        Line::from(Span::styled("Navigation", Style::default().fg(MAUVE).add_modifier(Modifier::BOLD))),
-        Line::from("  j/k      Scroll down/up"),
-        Line::from("  g/G      Go to top/bottom"),
-        Line::from("  ]/[      Next/prev annotation"),
-        Line::from("  Tab      Toggle editor/sidebar"),
-        Line::from(""),
-        Line::from(Span::styled("Annotations", Style::default().fg(MAUVE).add_modifier(Modifier::BOLD))),
-        Line::from("  v        Enter visual mode"),
-        Line::from("  a        Add annotation (after selection)"),
-        Line::from("  d        Delete annotation"),
-        Line::from("  r        Toggle resolved"),
+    let items: Vec<ListItem> = annotations
+        .iter()
+        .enumerate()
+        .map(|(i, ann)| {
+            let selected = i == app.chooser_selected;
+            let marker = if selected { ">" } else { " " };
+            let color = colors.severity(ann.severity);
+            let style = if selected {
+                colors.highlight().fg(color)
+            } else {
+                Style::default().fg(color)
+            };
+            let preview: String = ann.comment.chars().take(30).collect();
+            ListItem::new(format!(
+                "{} {} [{}] {}",
+                i + 1,
+                marker,
+                ann.severity.short(),
+                preview.replace('\n', " ")
+            ))
+            .style(style)
+        })
+        .collect();
+
+    let list = List::new(items).block(block);
+    frame.render_widget(list, area);
+}
+
+fn draw_detail(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
+    let (doc, ann) = match (&app.document, app.selected_annotation()) {
+        (Some(doc), Some(ann)) => (doc, ann),
+        _ => return,
+    };
+
+    let area = centered_rect(70, 22, frame.area());
+    clear_area(frame, &colors, area);
+
+    let color = colors.severity(ann.severity);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color))
+        .title(format!(
+            "Annotation {}/{} (j/k scroll, ]/[ next/prev, c suggest, Esc close)",
+            app.sidebar_selected + 1,
+            app.visible_annotations().len()
+        ));
+
+    let label = Style::default().fg(colors.subtext0);
+    let heading = Style::default()
+        .fg(colors.mauve)
+        .add_modifier(Modifier::BOLD);
+
+    let (row, col) = app.offset_to_cursor(ann.range.start_offset);
+    let status = if ann.is_resolved { "Resolved" } else { "Open" };
+    let category = ann.category.map(|c| c.as_str()).unwrap_or("None");
+
+    let (before, selected, after) = doc.excerpt(&ann.range, 160);
+    let context = if ann.is_general() {
+        vec![Line::from(Span::styled(
+            general_label(doc, ann),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))]
+    } else {
+        multiline_spans(&[
+            (format!("...{}", before), label),
+            (
+                if ann.range.is_point() {
+                    "‸".to_string()
+                } else {
+                    selected.to_string()
+                },
+                Style::default()
+                    .fg(color)
+                    .add_modifier(Modifier::UNDERLINED),
+            ),
+            (format!("{}...", after), label),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(
+                format!("[{}]", ann.severity.as_str()),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  Category: ", label),
+            Span::styled(category, Style::default().fg(colors.teal)),
+            Span::styled("  Status: ", label),
+            Span::styled(status, Style::default().fg(colors.text)),
+        ]),
+        Line::from(vec![
+            Span::styled("Line ", label),
+            Span::styled(
+                format!("{}:{}", row + 1, col + 1),
+                Style::default().fg(colors.text),
+            ),
+            Span::styled("  Created ", label),
+            Span::styled(
+                ann.created_at.format("%Y-%m-%d %H:%M").to_string(),
+                Style::default().fg(colors.text),
+            ),
+            Span::styled("  By ", label),
+            Span::styled(
+                ann.author.clone().unwrap_or_else(|| "unknown".to_string()),
+                Style::default().fg(colors.text),
+            ),
+        ]),
         Line::from(""),
-        Line::from(Span::styled("File", Style::default().fg(MAUVE).add_modifier(Modifier::BOLD))),
-        Line::from("  e        Export to ~/.quill/document.json"),
-        Line::from("  E        Export and show prompt"),
-        Line::from("  q        Quit"),
+        Line::from(Span::styled("Context", heading)),
+    ];
+    lines.extend(context);
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Comment", heading)));
+    lines.extend(ann.comment.lines().map(|l| {
+        Line::from(Span::styled(
+            l.to_string(),
+            Style::default().fg(colors.text),
+        ))
+    }));
+    if let Some(suggestion) = ann.suggestion.as_ref().filter(|_| ann.has_suggestion()) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Suggestion (A to apply)", heading)));
+        if suggestion.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "Delete this text",
+                Style::default().fg(colors.red),
+            )));
+        } else {
+            lines.extend(multiline_spans(&[(
+                suggestion.clone(),
+                Style::default().fg(colors.green),
+            )]));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((app.detail_scroll, 0));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_review(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
+    let review = &app.review;
+    let (Some(doc), Some(proposal)) = (&app.document, review.current()) else {
+        return;
+    };
+    let Some(ann) = doc
+        .annotations
+        .iter()
+        .find(|a| a.id == proposal.annotation_id)
+    else {
+        return;
+    };
+
+    let area = centered_rect(70, 22, frame.area());
+    clear_area(frame, &colors, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.mauve))
+        .title(format!(
+            "Revision {}/{} (a accept, x reject, A accept all, j/k move, Esc close)",
+            review.selected + 1,
+            review.proposals.len()
+        ));
+
+    let label = Style::default().fg(colors.subtext0);
+    let heading = Style::default()
+        .fg(colors.mauve)
+        .add_modifier(Modifier::BOLD);
+    let status_color = match proposal.status {
+        ProposalStatus::Pending => colors.yellow,
+        ProposalStatus::Accepted => colors.green,
+        ProposalStatus::Rejected => colors.red,
+    };
+    let color = colors.severity(ann.severity);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(
+                format!("[{}]", ann.severity.as_str()),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  Line ", label),
+            Span::styled(
+                doc.line_number(ann.range.start_offset).to_string(),
+                Style::default().fg(colors.text),
+            ),
+            Span::styled("  Status: ", label),
+            Span::styled(proposal.status.as_str(), Style::default().fg(status_color)),
+        ]),
         Line::from(""),
-        Line::from(Span::styled("Press any key to close", Style::default().fg(SUBTEXT0))),
+        Line::from(Span::styled("Comment", heading)),
     ];
+    lines.extend(ann.comment.lines().map(|l| {
+        Line::from(Span::styled(
+            l.to_string(),
+            Style::default().fg(colors.text),
+        ))
+    }));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Original", heading)));
+    lines.extend(multiline_spans(&[(
+        ann.selected_text.clone(),
+        Style::default()
+            .fg(colors.red)
+            .add_modifier(Modifier::CROSSED_OUT),
+    )]));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Revised", heading)));
+    lines.extend(multiline_spans(&[(
+        proposal.revised.clone(),
+        Style::default().fg(colors.green),
+    )]));
+    if !proposal.explanation.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Explanation", heading)));
+        lines.extend(multiline_spans(&[(proposal.explanation.clone(), label)]));
+    }
 
-    let paragraph = Paragraph::new(help_text).block(block);
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
 
-fn severity_color(severity: Severity) -> Color {
-    match severity {
-        Severity::MustFix => RED,
-        Severity::ShouldFix => YELLOW,
-        Severity::Consider => GREEN,
+fn draw_help(frame: &mut Frame, app: &App) {
+    let colors = Palette::new(&app.theme);
+    // Lay sections out in two columns generated from the active keymap
+    let mut sections: Vec<Vec<Line>> = Vec::new();
+    for (section, entries) in app.keymap.help_sections() {
+        let mut lines = vec![Line::from(Span::styled(
+            section,
+            Style::default()
+                .fg(colors.mauve)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(
+            entries.iter().map(|(keys, action)| {
+                Line::from(format!("  {:<10} {}", keys, action.description()))
+            }),
+        );
+        if lines.len() > 1 {
+            lines.push(Line::from(""));
+            sections.push(lines);
+        }
+    }
+
+    let total: usize = sections.iter().map(|s| s.len()).sum();
+    let mut left: Vec<Line> = Vec::new();
+    let mut right: Vec<Line> = Vec::new();
+    for lines in sections {
+        if left.len() < total / 2 {
+            left.extend(lines);
+        } else {
+            right.extend(lines);
+        }
+    }
+    right.push(Line::from(Span::styled(
+        "Mouse",
+        Style::default()
+            .fg(colors.mauve)
+            .add_modifier(Modifier::BOLD),
+    )));
+    right.push(Line::from("  Click to move, drag/double-click to select"));
+    right.push(Line::from(""));
+    right.push(Line::from(Span::styled(
+        "Press any key to close",
+        Style::default().fg(colors.subtext0),
+    )));
+
+    let height = left.len().max(right.len()) as u16 + 2;
+    let area = centered_rect(96, height, frame.area());
+    clear_area(frame, &colors, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(colors.blue))
+        .title("Help");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    frame.render_widget(Paragraph::new(left), columns[0]);
+    frame.render_widget(Paragraph::new(right), columns[1]);
+}
+
+/// Build lines from styled text fragments that may contain newlines
+fn multiline_spans(parts: &[(String, Style)]) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut current: Vec<Span> = Vec::new();
+    for (text, style) in parts {
+        for (i, piece) in text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Line::from(std::mem::take(&mut current)));
+            }
+            if !piece.is_empty() {
+                current.push(Span::styled(piece.to_string(), *style));
+            }
+        }
     }
+    lines.push(Line::from(current));
+    lines
+}
+
+/// Clear an overlay area, keeping the theme's background and text colors
+fn clear_area(frame: &mut Frame, colors: &Palette, area: Rect) {
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Block::default().style(Style::default().fg(colors.text).bg(colors.background)),
+        area,
+    );
 }
 
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
@@ -1,3 +1,3 @@
 pub mod navigation;
 
-pub use navigation::annotation_offset_by_index;
+pub use navigation::{annotation_offset_by_index, heading_offset_by_index};
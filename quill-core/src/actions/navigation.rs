@@ -1,8 +1,14 @@
-use crate::model::Document;
+use crate::model::{Annotation, Document};
+use crate::outline;
 
-/// Navigate to annotation by index in sorted list
-pub fn annotation_offset_by_index(doc: &Document, index: usize) -> Option<usize> {
-    doc.annotations_sorted()
+/// Navigate to annotation by index in the list shown in the sidebar
+pub fn annotation_offset_by_index(annotations: &[&Annotation], index: usize) -> Option<usize> {
+    annotations.get(index).map(|a| a.range.start_offset)
+}
+
+/// Navigate to heading by index in the document outline
+pub fn heading_offset_by_index(doc: &Document, index: usize) -> Option<usize> {
+    outline::parse_headings(&doc.content)
         .get(index)
-        .map(|a| a.range.start_offset)
+        .map(|h| h.start_offset)
 }
@@ -1,6 +1,21 @@
+use std::collections::HashSet;
+
 use crate::actions;
+use crate::config::Config;
 use crate::cursor::CursorState;
-use crate::model::{Annotation, Category, Document, Severity, TextRange};
+use crate::diff::Revision;
+use crate::export::{join_prompts, PromptOptions};
+use crate::filter::AnnotationFilter;
+use crate::keymap::Keymap;
+use crate::model::{Annotation, Category, Document, Scope, Severity, TextRange};
+use crate::outline::{self, Heading};
+use crate::preview::Preview;
+use crate::response::Review;
+use crate::send::{SendConfig, SendOutput};
+use crate::sidecar::orphaned_warning;
+use crate::sort::SortOrder;
+use crate::template::Template;
+use crate::theme::Theme;
 
 /// Application mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,6 +26,17 @@ pub enum Mode {
     CategoryPicker,
     SeverityPicker,
     Help,
+    Detail,
+    /// Picking one of several annotations under the cursor
+    Chooser,
+    /// Full-screen prompt preview
+    Preview,
+    /// Accepting or rejecting revisions from a model response
+    Review,
+    /// Output of the command the prompt was sent to
+    Output,
+    /// Reviewing a revised version of the document hunk by hunk
+    Diff,
 }
 
 /// Focus area
@@ -18,6 +44,7 @@ pub enum Mode {
 pub enum Focus {
     Editor,
     Sidebar,
+    Outline,
 }
 
 /// Input target for text input mode
@@ -25,6 +52,88 @@ pub enum Focus {
 pub enum InputTarget {
     Comment,
     FilePath,
+    /// Path of a model response to review
+    ResponsePath,
+    /// Path of a revised version of the document
+    RevisionPath,
+    /// Replacement text suggested for an annotation
+    Suggestion,
+    Search,
+    /// `:` command line
+    Command,
+}
+
+/// How annotation comments are shown as virtual text in the editor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InlineComments {
+    #[default]
+    Off,
+    /// After the last line of the annotated range
+    EndOfLine,
+    /// As a block beneath the paragraph containing the range
+    Below,
+}
+
+impl InlineComments {
+    pub fn next(&self) -> InlineComments {
+        match self {
+            InlineComments::Off => InlineComments::EndOfLine,
+            InlineComments::EndOfLine => InlineComments::Below,
+            InlineComments::Below => InlineComments::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InlineComments::Off => "off",
+            InlineComments::EndOfLine => "end of line",
+            InlineComments::Below => "below paragraph",
+        }
+    }
+}
+
+/// Line number display in the editor gutter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GutterMode {
+    #[default]
+    Off,
+    Absolute,
+    /// Distance from the cursor line (cursor line shows its absolute number)
+    Relative,
+}
+
+impl GutterMode {
+    pub fn next(&self) -> GutterMode {
+        match self {
+            GutterMode::Off => GutterMode::Absolute,
+            GutterMode::Absolute => GutterMode::Relative,
+            GutterMode::Relative => GutterMode::Off,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GutterMode::Off => "off",
+            GutterMode::Absolute => "absolute",
+            GutterMode::Relative => "relative",
+        }
+    }
+}
+
+/// A row in the sidebar list
+#[derive(Debug, Clone)]
+pub enum SidebarRow<'a> {
+    /// Group header (only when grouping is enabled)
+    Header {
+        label: String,
+        count: usize,
+        collapsed: bool,
+    },
+    /// Annotation at `index` in `App::visible_annotations`
+    Item {
+        index: usize,
+        annotation: &'a Annotation,
+    },
 }
 
 /// Platform-agnostic application state
@@ -34,6 +143,14 @@ pub struct App {
     pub mode: Mode,
     pub focus: Focus,
     pub running: bool,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    /// Themes available for selection, built-in first
+    pub themes: Vec<Theme>,
+    /// Template used to generate prompts
+    pub template: Template,
+    /// Prompt templates available for selection, the built-in default first
+    pub templates: Vec<Template>,
 
     // Selection state
     pub selection_start: Option<(usize, usize)>, // (row, col)
@@ -41,10 +158,48 @@ pub struct App {
 
     // Sidebar state
     pub sidebar_selected: usize,
+    pub filter: AnnotationFilter,
+    /// Restrict exports to annotations passing the filter
+    pub export_scoped: bool,
+    pub prompt_options: PromptOptions,
+    pub sort_order: SortOrder,
+    /// Show sidebar annotations under group headers (when sort order groups)
+    pub group_annotations: bool,
+    pub collapsed_groups: HashSet<String>,
+
+    /// Author recorded on new annotations
+    pub author: Option<String>,
+
+    pub inline_comments: InlineComments,
+    pub gutter: GutterMode,
+
+    // Chooser state (index into annotations at cursor)
+    pub chooser_selected: usize,
+
+    // Detail pane state
+    pub detail_scroll: u16,
+    /// Full-screen prompt preview state
+    pub preview: Preview,
+    /// Proposed revisions being reviewed
+    pub review: Review,
+    /// Document text changed since it was loaded or saved
+    pub content_modified: bool,
+    /// Command the send action pipes prompts to
+    pub send: SendConfig,
+    pub output: SendOutput,
+    /// Revised version being compared with the document
+    pub revision: Revision,
+
+    // Outline state
+    pub outline_visible: bool,
+    pub outline_selected: usize,
 
     // Input state
     pub input_buffer: String,
     pub input_target: InputTarget,
+    /// Command-line completions being cycled with Tab
+    pub completions: Vec<String>,
+    pub completion_index: usize,
 
     // Picker state
     pub category_selected: usize,
@@ -52,6 +207,7 @@ pub struct App {
 
     // Pending annotation (during creation workflow)
     pub pending_range: Option<TextRange>,
+    pub pending_scope: Scope,
     pub pending_category: Option<Category>,
     pub pending_severity: Severity,
 
@@ -67,19 +223,51 @@ impl App {
             mode: Mode::Normal,
             focus: Focus::Editor,
             running: true,
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            themes: Theme::builtin(),
+            template: Template::default(),
+            templates: vec![Template::default()],
 
             selection_start: None,
             selection_end: None,
 
             sidebar_selected: 0,
+            filter: AnnotationFilter::default(),
+            export_scoped: false,
+            prompt_options: PromptOptions::default(),
+            sort_order: SortOrder::Position,
+            group_annotations: false,
+            collapsed_groups: HashSet::new(),
+
+            author: None,
+
+            inline_comments: InlineComments::Off,
+            gutter: GutterMode::Off,
+
+            chooser_selected: 0,
+
+            detail_scroll: 0,
+            preview: Preview::default(),
+            review: Review::default(),
+            content_modified: false,
+            send: SendConfig::default(),
+            output: SendOutput::default(),
+            revision: Revision::default(),
+
+            outline_visible: false,
+            outline_selected: 0,
 
             input_buffer: String::new(),
             input_target: InputTarget::Comment,
+            completions: Vec::new(),
+            completion_index: 0,
 
             category_selected: 0,
             severity_selected: 1, // Default to ShouldFix
 
             pending_range: None,
+            pending_scope: Scope::Range,
             pending_category: None,
             pending_severity: Severity::ShouldFix,
 
@@ -87,10 +275,111 @@ impl App {
         }
     }
 
+    /// Apply user configuration, returning a message for each setting that was ignored
+    pub fn apply_config(&mut self, config: &Config) -> Vec<String> {
+        let mut errors = self.keymap.apply_overrides(&config.keys);
+
+        for (name, spec) in &config.themes {
+            match Theme::from_spec(name, spec, &self.themes) {
+                Ok(theme) => {
+                    self.themes.retain(|t| t.name != theme.name);
+                    self.themes.push(theme);
+                }
+                Err(e) => errors.push(format!("Theme {}: {}", name, e)),
+            }
+        }
+        if let Some(name) = &config.theme {
+            if !self.set_theme(name) {
+                errors.push(format!("Unknown theme: {}", name));
+            }
+        }
+
+        for (name, source) in &config.templates {
+            if let Err(e) = self.add_template(name, source) {
+                errors.push(format!("Template {}: {}", name, e));
+            }
+        }
+        if let Some(name) = &config.template {
+            if !self.set_template(name) {
+                errors.push(format!("Unknown template: {}", name));
+            }
+        }
+
+        if config.send.command.is_some() {
+            self.send = config.send.clone();
+        }
+        errors
+    }
+
+    /// Parse and register a prompt template, replacing any with the same name
+    pub fn add_template(&mut self, name: &str, source: &str) -> Result<(), String> {
+        let template = Template::parse(name, source)?;
+        self.templates.retain(|t| t.name != name);
+        self.templates.push(template);
+        Ok(())
+    }
+
+    /// Switch to a prompt template by name, returning false if there is none
+    pub fn set_template(&mut self, name: &str) -> bool {
+        match self.templates.iter().find(|t| t.name == name) {
+            Some(template) => {
+                self.template = template.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cycle through the available prompt templates
+    pub fn cycle_template(&mut self) {
+        let idx = self
+            .templates
+            .iter()
+            .position(|t| t.name == self.template.name)
+            .map_or(0, |i| (i + 1) % self.templates.len());
+        self.template = self.templates[idx].clone();
+        self.set_status(&format!("Template: {}", self.template.name));
+    }
+
+    /// Switch to a theme by name, returning false if there is none
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        match self.themes.iter().find(|t| t.name == name) {
+            Some(theme) => {
+                self.theme = theme.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cycle through the available themes
+    pub fn cycle_theme(&mut self) {
+        let idx = self
+            .themes
+            .iter()
+            .position(|t| t.name == self.theme.name)
+            .map_or(0, |i| (i + 1) % self.themes.len());
+        self.theme = self.themes[idx].clone();
+        self.set_status(&format!("Theme: {}", self.theme.name));
+    }
+
     pub fn load_document(&mut self, doc: Document) {
         self.cursor.set_content(&doc.content);
         self.document = Some(doc);
+        self.content_modified = false;
         self.sidebar_selected = 0;
+        self.outline_selected = 0;
+    }
+
+    /// Load a document opened from `name`, warning about annotations its
+    /// sidecar could not restore
+    pub fn open_document(&mut self, doc: Document, name: &str) {
+        let status = match orphaned_warning(&doc) {
+            Some(warning) => format!("Loaded {}; {}", name, warning),
+            None => format!("Loaded {}", name),
+        };
+        self.load_document(doc);
+        self.set_status(&status);
     }
 
     /// Get cursor position as (row, col)
@@ -146,6 +435,33 @@ impl App {
         self.cursor.move_word_back();
     }
 
+    /// Move cursor to (row, col), e.g. from a mouse click, extending any active selection
+    pub fn place_cursor(&mut self, row: usize, col: usize) {
+        self.cursor.set_position(row, col);
+        self.update_selection();
+    }
+
+    /// Extend a selection from the cursor to (row, col), entering visual mode if needed
+    pub fn extend_selection_to(&mut self, row: usize, col: usize) {
+        if self.mode != Mode::Visual {
+            self.enter_visual_mode();
+        }
+        self.place_cursor(row, col);
+    }
+
+    /// Select the word under the cursor in visual mode
+    pub fn select_word_at_cursor(&mut self) {
+        let (row, col) = self.cursor_pos();
+        let (start, end) = self.cursor.word_bounds(row, col);
+        if start == end {
+            return;
+        }
+        self.mode = Mode::Visual;
+        self.selection_start = Some((row, start));
+        self.cursor.set_position(row, end);
+        self.selection_end = Some((row, end));
+    }
+
     /// Enter visual/selection mode
     pub fn enter_visual_mode(&mut self) {
         self.mode = Mode::Visual;
@@ -154,7 +470,8 @@ impl App {
         self.selection_end = Some(cursor);
     }
 
-    /// Exit visual mode and get selection range
+    /// Exit visual mode and get selection range, which is an insertion point
+    /// if nothing was selected
     pub fn exit_visual_mode(&mut self) -> Option<TextRange> {
         if self.mode != Mode::Visual {
             return None;
@@ -170,11 +487,7 @@ impl App {
         self.selection_start = None;
         self.selection_end = None;
 
-        if start_offset != end_offset {
-            Some(TextRange::new(start_offset, end_offset))
-        } else {
-            None
-        }
+        Some(TextRange::new(start_offset, end_offset))
     }
 
     /// Update selection end position
@@ -203,10 +516,54 @@ impl App {
     pub fn start_annotation(&mut self) {
         if let Some(range) = self.exit_visual_mode() {
             self.pending_range = Some(range);
+            self.pending_scope = Scope::Range;
             self.mode = Mode::SeverityPicker;
         }
     }
 
+    /// Start annotating the point at the cursor, for text to be inserted there
+    pub fn start_point_annotation(&mut self) {
+        if self.document.is_none() {
+            return;
+        }
+        let (row, col) = self.cursor_pos();
+        let offset = self.cursor_to_offset(row, col);
+        self.pending_range = Some(TextRange::new(offset, offset));
+        self.pending_scope = Scope::Range;
+        self.mode = Mode::SeverityPicker;
+    }
+
+    /// Start a comment on the whole document, or on the section containing the
+    /// cursor (the selected heading when the outline has focus)
+    pub fn start_general_comment(&mut self, scope: Scope) {
+        let Some(doc) = &self.document else {
+            return;
+        };
+        let range = match scope {
+            Scope::Range => return,
+            Scope::Document => TextRange::new(0, 0),
+            Scope::Section => {
+                let headings = self.headings();
+                let heading = if self.focus == Focus::Outline {
+                    headings.get(self.outline_selected)
+                } else {
+                    let (row, col) = self.cursor_pos();
+                    let offset = self.cursor_to_offset(row, col);
+                    // The innermost section is the last one starting before the cursor
+                    headings.iter().rev().find(|h| h.contains(offset))
+                };
+                let Some(heading) = heading else {
+                    self.set_status("No heading above the cursor");
+                    return;
+                };
+                heading.line_range(&doc.content)
+            }
+        };
+        self.pending_range = Some(range);
+        self.pending_scope = scope;
+        self.mode = Mode::SeverityPicker;
+    }
+
     /// Complete annotation creation
     pub fn complete_annotation(&mut self) -> bool {
         let range = match self.pending_range.take() {
@@ -223,6 +580,8 @@ impl App {
         let mut annotation = Annotation::new(range, selected_text, self.input_buffer.clone());
         annotation.category = self.pending_category;
         annotation.severity = self.pending_severity;
+        annotation.author = self.author.clone();
+        annotation.scope = std::mem::take(&mut self.pending_scope);
 
         doc.add_annotation(annotation);
 
@@ -236,41 +595,456 @@ impl App {
         true
     }
 
+    /// Whether the sidebar currently shows group headers
+    pub fn is_grouped(&self) -> bool {
+        self.group_annotations && self.sort_order != SortOrder::Position
+    }
+
+    /// Get annotations passing the filter, in sidebar sort order
+    pub fn filtered_annotations(&self) -> Vec<&Annotation> {
+        self.document
+            .as_ref()
+            .map(|d| {
+                d.annotations_sorted_by(self.sort_order)
+                    .into_iter()
+                    .filter(|a| self.filter.matches(a))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get annotations shown in the sidebar (sorted, filtered, outside collapsed groups)
+    pub fn visible_annotations(&self) -> Vec<&Annotation> {
+        let mut annotations = self.filtered_annotations();
+        if self.is_grouped() {
+            annotations.retain(|a| !self.is_collapsed(a));
+        }
+        annotations
+    }
+
+    fn is_collapsed(&self, ann: &Annotation) -> bool {
+        self.sort_order
+            .group_label(ann)
+            .is_some_and(|label| self.collapsed_groups.contains(&label))
+    }
+
+    /// Get sidebar rows, with group headers when grouping is enabled
+    pub fn sidebar_rows(&self) -> Vec<SidebarRow<'_>> {
+        let mut rows = Vec::new();
+        let mut index = 0;
+        let mut current_group: Option<String> = None;
+        let annotations = self.filtered_annotations();
+
+        for ann in &annotations {
+            let label = if self.is_grouped() {
+                self.sort_order.group_label(ann)
+            } else {
+                None
+            };
+
+            if let Some(label) = label {
+                let collapsed = self.collapsed_groups.contains(&label);
+                if current_group.as_ref() != Some(&label) {
+                    let count = annotations
+                        .iter()
+                        .filter(|a| self.sort_order.group_label(a).as_ref() == Some(&label))
+                        .count();
+                    rows.push(SidebarRow::Header {
+                        label: label.clone(),
+                        count,
+                        collapsed,
+                    });
+                    current_group = Some(label);
+                }
+                if collapsed {
+                    continue;
+                }
+            }
+
+            rows.push(SidebarRow::Item {
+                index,
+                annotation: ann,
+            });
+            index += 1;
+        }
+
+        rows
+    }
+
     /// Get currently selected annotation
     pub fn selected_annotation(&self) -> Option<&Annotation> {
-        let doc = self.document.as_ref()?;
-        let sorted = doc.annotations_sorted();
-        sorted.get(self.sidebar_selected).copied()
+        self.visible_annotations()
+            .get(self.sidebar_selected)
+            .copied()
     }
 
     /// Navigate to next annotation
     pub fn next_annotation(&mut self) {
-        if let Some(doc) = &self.document {
-            let count = doc.annotations.len();
-            if count > 0 {
-                self.sidebar_selected = (self.sidebar_selected + 1) % count;
-                if let Some(offset) = actions::annotation_offset_by_index(doc, self.sidebar_selected) {
-                    self.set_cursor_offset(offset);
-                }
-            }
+        let count = self.visible_annotations().len();
+        if count > 0 {
+            self.sidebar_selected = (self.sidebar_selected + 1) % count;
+            self.jump_to_selected_annotation();
         }
     }
 
     /// Navigate to previous annotation
     pub fn prev_annotation(&mut self) {
-        if let Some(doc) = &self.document {
-            let count = doc.annotations.len();
-            if count > 0 {
-                self.sidebar_selected = if self.sidebar_selected == 0 {
-                    count - 1
-                } else {
-                    self.sidebar_selected - 1
-                };
-                if let Some(offset) = actions::annotation_offset_by_index(doc, self.sidebar_selected) {
-                    self.set_cursor_offset(offset);
+        let count = self.visible_annotations().len();
+        if count > 0 {
+            self.sidebar_selected = if self.sidebar_selected == 0 {
+                count - 1
+            } else {
+                self.sidebar_selected - 1
+            };
+            self.jump_to_selected_annotation();
+        }
+    }
+
+    /// Select an annotation by its index in the sidebar and move the cursor to it
+    pub fn select_annotation(&mut self, index: usize) {
+        if index < self.visible_annotations().len() {
+            self.sidebar_selected = index;
+            self.jump_to_selected_annotation();
+        }
+    }
+
+    fn jump_to_selected_annotation(&mut self) {
+        let offset =
+            actions::annotation_offset_by_index(&self.visible_annotations(), self.sidebar_selected);
+        if let Some(offset) = offset {
+            self.set_cursor_offset(offset);
+        }
+    }
+
+    /// Select the annotation with the given id if it is visible
+    fn reselect(&mut self, id: Option<uuid::Uuid>) {
+        let position = id.and_then(|id| self.visible_annotations().iter().position(|a| a.id == id));
+        match position {
+            Some(pos) => self.sidebar_selected = pos,
+            None => self.clamp_sidebar_selection(),
+        }
+    }
+
+    /// Cycle the sidebar sort order, keeping the selected annotation selected
+    pub fn cycle_sort_order(&mut self) {
+        self.set_sort_order(self.sort_order.next());
+    }
+
+    /// Change the sidebar sort order, keeping the selected annotation
+    pub fn set_sort_order(&mut self, order: SortOrder) {
+        let selected = self.selected_annotation().map(|a| a.id);
+        self.sort_order = order;
+        self.collapsed_groups.clear();
+        self.reselect(selected);
+        self.set_status(&format!("Sort by {}", self.sort_order.as_str()));
+    }
+
+    /// Toggle grouping of sidebar annotations under headers
+    pub fn toggle_grouping(&mut self) {
+        let selected = self.selected_annotation().map(|a| a.id);
+        self.group_annotations = !self.group_annotations;
+        self.reselect(selected);
+        if !self.group_annotations {
+            self.set_status("Grouping off");
+        } else if self.sort_order == SortOrder::Position {
+            self.set_status("Grouping on (choose a sort order other than Position)");
+        } else {
+            self.set_status(&format!("Grouped by {}", self.sort_order.as_str()));
+        }
+    }
+
+    /// Collapse the group containing the selected annotation
+    pub fn collapse_selected_group(&mut self) {
+        if !self.is_grouped() {
+            return;
+        }
+        let label = match self.selected_annotation() {
+            Some(ann) => self.sort_order.group_label(ann),
+            None => None,
+        };
+        if let Some(label) = label {
+            self.set_status(&format!("Collapsed {}", label));
+            self.collapsed_groups.insert(label);
+            self.clamp_sidebar_selection();
+        }
+    }
+
+    /// Expand all collapsed groups
+    pub fn expand_all_groups(&mut self) {
+        let selected = self.selected_annotation().map(|a| a.id);
+        self.collapsed_groups.clear();
+        self.reselect(selected);
+    }
+
+    /// Keep sidebar selection within the visible annotations
+    pub(crate) fn clamp_sidebar_selection(&mut self) {
+        let count = self.visible_annotations().len();
+        if self.sidebar_selected >= count {
+            self.sidebar_selected = count.saturating_sub(1);
+        }
+    }
+
+    /// Toggle hiding of resolved annotations
+    pub fn toggle_hide_resolved(&mut self) {
+        self.filter.hide_resolved = !self.filter.hide_resolved;
+        self.filter_changed();
+    }
+
+    /// Cycle the severity filter
+    pub fn cycle_severity_filter(&mut self) {
+        self.filter.cycle_severity();
+        self.filter_changed();
+    }
+
+    /// Cycle the category filter
+    pub fn cycle_category_filter(&mut self) {
+        self.filter.cycle_category();
+        self.filter_changed();
+    }
+
+    /// Set the comment search text
+    pub fn set_filter_query(&mut self, query: &str) {
+        self.filter.query = query.trim().to_string();
+        self.filter_changed();
+    }
+
+    /// Remove all filter criteria
+    pub fn clear_filter(&mut self) {
+        self.filter = AnnotationFilter::default();
+        self.filter_changed();
+    }
+
+    /// Toggle whether exports only include filtered annotations
+    pub fn toggle_export_scoped(&mut self) {
+        self.export_scoped = !self.export_scoped;
+        if self.export_scoped {
+            self.set_status("Exports limited to current filter");
+        } else {
+            self.set_status("Exports include all annotations");
+        }
+    }
+
+    /// Get the document to export, scoped to the filter if enabled
+    pub fn export_document(&self) -> Option<Document> {
+        let doc = self.document.as_ref()?;
+        if self.export_scoped {
+            Some(self.filter.apply(doc))
+        } else {
+            Some(doc.clone())
+        }
+    }
+
+    /// Generate the revision prompts for the export document with the current
+    /// template and options; more than one when split to fit the token budget
+    pub fn prompt_parts(&self) -> Option<Vec<String>> {
+        let doc = self.export_document()?;
+        Some(self.template.render_all(&doc, &self.prompt_options))
+    }
+
+    /// Generate a prompt for just the selected annotation with the current
+    /// template and options
+    pub fn annotation_prompt(&self) -> Option<String> {
+        let doc = self.document.as_ref()?;
+        let mut ann = self.selected_annotation()?.clone();
+        ann.is_resolved = false;
+        let single = Document {
+            annotations: vec![ann],
+            orphaned: Vec::new(),
+            ..doc.clone()
+        };
+        Some(self.template.render(&single, &self.prompt_options))
+    }
+
+    /// Generate the revision prompt text, joining any split prompts
+    pub fn prompt_text(&self) -> Option<String> {
+        self.prompt_parts().map(|parts| join_prompts(&parts))
+    }
+
+    /// Replace the sidebar filter
+    pub fn set_filter(&mut self, filter: AnnotationFilter) {
+        self.filter = filter;
+        self.filter_changed();
+    }
+
+    fn filter_changed(&mut self) {
+        self.sidebar_selected = 0;
+        if self.filter.is_active() {
+            let msg = format!(
+                "Filter: {} ({} shown)",
+                self.filter.describe(),
+                self.visible_annotations().len()
+            );
+            self.set_status(&msg);
+        } else {
+            self.set_status("Filter cleared");
+        }
+    }
+
+    /// Cycle the gutter line number mode
+    pub fn cycle_gutter(&mut self) {
+        self.gutter = self.gutter.next();
+        self.set_status(&format!("Line numbers: {}", self.gutter.as_str()));
+    }
+
+    /// Get the gutter label for a line, if the gutter is shown
+    pub fn line_number_label(&self, line: usize) -> Option<usize> {
+        let (row, _) = self.cursor_pos();
+        match self.gutter {
+            GutterMode::Off => None,
+            GutterMode::Absolute => Some(line + 1),
+            GutterMode::Relative if line == row => Some(line + 1),
+            GutterMode::Relative => Some(line.abs_diff(row)),
+        }
+    }
+
+    /// Get the most severe unresolved annotation severity touching each line
+    pub fn line_markers(&self) -> Vec<Option<Severity>> {
+        let mut markers = vec![None; self.cursor.line_count()];
+        let doc = match &self.document {
+            Some(d) => d,
+            None => return markers,
+        };
+
+        for ann in doc
+            .annotations
+            .iter()
+            .filter(|a| !a.is_resolved && !a.is_general())
+        {
+            let first = self.offset_to_cursor(ann.range.start_offset).0;
+            let last = self
+                .offset_to_cursor(
+                    ann.range
+                        .end_offset
+                        .saturating_sub(1)
+                        .max(ann.range.start_offset),
+                )
+                .0;
+            for marker in markers.iter_mut().take(last + 1).skip(first) {
+                if marker.is_none_or(|current: Severity| ann.severity.rank() < current.rank()) {
+                    *marker = Some(ann.severity);
                 }
             }
         }
+        markers
+    }
+
+    /// Cycle how comments are shown inline in the editor
+    pub fn cycle_inline_comments(&mut self) {
+        self.inline_comments = self.inline_comments.next();
+        self.set_status(&format!(
+            "Inline comments: {}",
+            self.inline_comments.as_str()
+        ));
+    }
+
+    /// Unresolved annotations with suggestions, paired with the line the
+    /// suggested text is shown after, ordered by that line
+    pub fn suggestion_anchors(&self) -> Vec<(usize, &Annotation)> {
+        let Some(doc) = &self.document else {
+            return Vec::new();
+        };
+        let mut anchors: Vec<_> = doc
+            .annotations_sorted()
+            .into_iter()
+            .filter(|a| !a.is_resolved && !a.is_general() && a.has_suggestion())
+            .map(|a| {
+                let last = a
+                    .range
+                    .end_offset
+                    .saturating_sub(1)
+                    .max(a.range.start_offset);
+                (self.offset_to_cursor(last).0, a)
+            })
+            .collect();
+        anchors.sort_by_key(|(line, _)| *line);
+        anchors
+    }
+
+    /// Get unresolved annotations paired with the line their inline comment
+    /// is shown after, ordered by that line
+    pub fn inline_comment_anchors(&self) -> Vec<(usize, &Annotation)> {
+        let doc = match &self.document {
+            Some(d) if self.inline_comments != InlineComments::Off => d,
+            _ => return Vec::new(),
+        };
+
+        let mut anchors: Vec<_> = doc
+            .annotations_sorted()
+            .into_iter()
+            .filter(|a| !a.is_resolved && !a.is_general())
+            .map(|a| {
+                let last = a
+                    .range
+                    .end_offset
+                    .saturating_sub(1)
+                    .max(a.range.start_offset);
+                let mut line = self.offset_to_cursor(last).0;
+                if self.inline_comments == InlineComments::Below {
+                    // Advance to the last line before a blank line
+                    while self
+                        .cursor
+                        .line(line + 1)
+                        .is_some_and(|l| !l.trim().is_empty())
+                    {
+                        line += 1;
+                    }
+                }
+                (line, a)
+            })
+            .collect();
+        anchors.sort_by_key(|(line, _)| *line);
+        anchors
+    }
+
+    /// Get annotations containing the cursor, sorted by start offset
+    pub fn annotations_at_cursor(&self) -> Vec<&Annotation> {
+        let (row, col) = self.cursor_pos();
+        let offset = self.cursor_to_offset(row, col);
+        self.document
+            .as_ref()
+            .map(|d| d.annotations_at(offset))
+            .unwrap_or_default()
+    }
+
+    /// Select the annotation under the cursor, asking which one if several overlap
+    pub fn select_at_cursor(&mut self) {
+        match self.annotations_at_cursor().len() {
+            0 => self.set_status("No annotation at cursor"),
+            1 => self.choose_at_cursor(0),
+            _ => {
+                self.chooser_selected = 0;
+                self.mode = Mode::Chooser;
+            }
+        }
+    }
+
+    /// Select the `index`th annotation under the cursor in the sidebar
+    pub fn choose_at_cursor(&mut self, index: usize) {
+        self.mode = Mode::Normal;
+        let id = match self.annotations_at_cursor().get(index) {
+            Some(a) => a.id,
+            None => return,
+        };
+        match self.visible_annotations().iter().position(|a| a.id == id) {
+            Some(pos) => {
+                self.sidebar_selected = pos;
+                self.set_status(&format!("Selected annotation {}", pos + 1));
+            }
+            None => self.set_status("Annotation is hidden by the sidebar filter"),
+        }
+    }
+
+    /// Open the detail pane for the selected annotation
+    pub fn open_detail(&mut self) {
+        if self.selected_annotation().is_some() {
+            self.detail_scroll = 0;
+            self.mode = Mode::Detail;
+        }
+    }
+
+    /// Close the detail pane
+    pub fn close_detail(&mut self) {
+        self.mode = Mode::Normal;
     }
 
     /// Delete selected annotation
@@ -282,11 +1056,7 @@ impl App {
 
         if let Some(doc) = self.document.as_mut() {
             if doc.remove_annotation(id).is_some() {
-                // Adjust selection if needed
-                let count = doc.annotations.len();
-                if self.sidebar_selected >= count && count > 0 {
-                    self.sidebar_selected = count - 1;
-                }
+                self.clamp_sidebar_selection();
                 self.set_status("Annotation deleted");
                 return true;
             }
@@ -303,6 +1073,8 @@ impl App {
 
         if let Some(doc) = self.document.as_mut() {
             if doc.toggle_resolved(id) {
+                // Resolving may hide the annotation from a filtered sidebar
+                self.clamp_sidebar_selection();
                 self.set_status("Toggled resolved status");
                 return true;
             }
@@ -310,6 +1082,117 @@ impl App {
         false
     }
 
+    /// Prompt for the selected annotation's suggested replacement text,
+    /// starting from its current suggestion or the annotated text
+    pub fn start_suggestion(&mut self) {
+        let Some(ann) = self.selected_annotation() else {
+            self.set_status("No annotation selected");
+            return;
+        };
+        if ann.is_general() {
+            self.set_status("General comments have no text to replace");
+            return;
+        }
+        self.input_buffer = ann
+            .suggestion
+            .clone()
+            .unwrap_or_else(|| ann.selected_text.clone());
+        self.input_target = InputTarget::Suggestion;
+        self.mode = Mode::Input;
+    }
+
+    /// Set or clear the selected annotation's suggestion
+    pub fn set_suggestion(&mut self, suggestion: Option<String>) -> bool {
+        let Some(id) = self
+            .selected_annotation()
+            .filter(|a| !a.is_general())
+            .map(|a| a.id)
+        else {
+            return false;
+        };
+        let Some(doc) = self.document.as_mut() else {
+            return false;
+        };
+        let Some(ann) = doc.annotations.iter_mut().find(|a| a.id == id) else {
+            return false;
+        };
+        let status = match &suggestion {
+            Some(s) if s.is_empty() => "Suggested deleting the text",
+            Some(_) => "Suggestion saved",
+            None => "Suggestion cleared",
+        };
+        ann.suggestion = suggestion;
+        doc.updated_at = chrono::Utc::now();
+        self.set_status(status);
+        true
+    }
+
+    /// Replace the selected annotation's text with its suggestion and resolve it
+    pub fn apply_suggestion(&mut self) -> bool {
+        let Some(ann) = self.selected_annotation() else {
+            self.set_status("No annotation selected");
+            return false;
+        };
+        let (id, range, text) = (ann.id, ann.range.clone(), ann.selected_text.clone());
+        let Some(suggestion) = ann.suggestion.clone() else {
+            self.set_status("Annotation has no suggestion");
+            return false;
+        };
+        let Some(doc) = self.document.as_mut() else {
+            return false;
+        };
+        if doc.content.get(range.start_offset..range.end_offset) != Some(text.as_str()) {
+            self.set_status("Annotated text has changed; suggestion not applied");
+            return false;
+        }
+        doc.replace_range(&range, &suggestion);
+        if let Some(ann) = doc.annotations.iter_mut().find(|a| a.id == id) {
+            ann.is_resolved = true;
+            ann.suggestion = None;
+        }
+        self.content_changed();
+        self.set_status("Suggestion applied (:w to save, :q! to discard)");
+        true
+    }
+
+    /// Refresh state after the document text was edited in place
+    pub(crate) fn content_changed(&mut self) {
+        let Some(doc) = &self.document else {
+            return;
+        };
+        let (row, col) = self.cursor.cursor();
+        self.cursor.set_content(&doc.content);
+        self.cursor.set_position(row, col);
+        self.content_modified = true;
+        // Resolved annotations may leave a filtered sidebar
+        self.clamp_sidebar_selection();
+    }
+
+    /// Resolve every unresolved annotation matching a filter, returning how many changed
+    pub fn resolve_matching(&mut self, filter: &AnnotationFilter) -> usize {
+        let Some(doc) = self.document.as_mut() else {
+            return 0;
+        };
+        let mut count = 0;
+        for ann in doc.annotations.iter_mut() {
+            if !ann.is_resolved && filter.matches(ann) {
+                ann.is_resolved = true;
+                count += 1;
+            }
+        }
+        if count > 0 {
+            doc.updated_at = chrono::Utc::now();
+            self.clamp_sidebar_selection();
+        }
+        count
+    }
+
+    /// Move the cursor to the start of a 1-based line number
+    pub fn goto_line(&mut self, line: usize) {
+        self.focus = Focus::Editor;
+        self.place_cursor(line.saturating_sub(1), 0);
+    }
+
     /// Set status message
     pub fn set_status(&mut self, msg: &str) {
         self.status_message = Some(msg.to_string());
@@ -320,14 +1203,71 @@ impl App {
         self.status_message = None;
     }
 
-    /// Toggle focus between editor and sidebar
+    /// Cycle focus between editor, sidebar and (when visible) outline
     pub fn toggle_focus(&mut self) {
         self.focus = match self.focus {
             Focus::Editor => Focus::Sidebar,
-            Focus::Sidebar => Focus::Editor,
+            Focus::Sidebar if self.outline_visible => Focus::Outline,
+            Focus::Sidebar | Focus::Outline => Focus::Editor,
         };
     }
 
+    /// Get the document outline
+    pub fn headings(&self) -> Vec<Heading> {
+        self.document
+            .as_ref()
+            .map(|d| outline::parse_headings(&d.content))
+            .unwrap_or_default()
+    }
+
+    /// Show or hide the outline pane, focusing it when shown
+    pub fn toggle_outline(&mut self) {
+        self.outline_visible = !self.outline_visible;
+        if self.outline_visible {
+            self.focus = Focus::Outline;
+            // Start from the section containing the cursor
+            let (row, _) = self.cursor_pos();
+            self.outline_selected = self
+                .headings()
+                .iter()
+                .rposition(|h| h.line <= row)
+                .unwrap_or(0);
+        } else if self.focus == Focus::Outline {
+            self.focus = Focus::Editor;
+        }
+    }
+
+    /// Navigate to next heading in the outline
+    pub fn next_heading(&mut self) {
+        let count = self.headings().len();
+        if count > 0 {
+            self.outline_selected = (self.outline_selected + 1) % count;
+            self.jump_to_selected_heading();
+        }
+    }
+
+    /// Navigate to previous heading in the outline
+    pub fn prev_heading(&mut self) {
+        let count = self.headings().len();
+        if count > 0 {
+            self.outline_selected = if self.outline_selected == 0 {
+                count - 1
+            } else {
+                self.outline_selected - 1
+            };
+            self.jump_to_selected_heading();
+        }
+    }
+
+    /// Move cursor to the selected outline heading
+    pub fn jump_to_selected_heading(&mut self) {
+        if let Some(doc) = &self.document {
+            if let Some(offset) = actions::heading_offset_by_index(doc, self.outline_selected) {
+                self.set_cursor_offset(offset);
+            }
+        }
+    }
+
     /// Get title for display
     pub fn title(&self) -> String {
         self.document
@@ -350,3 +1290,227 @@ impl Default for App {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::UNSAVED_CHANGES;
+
+    #[test]
+    fn test_grouped_sidebar_matches_selection() {
+        let mut doc = Document::new("Test".to_string(), "abcdefghij".to_string());
+        for (start, severity) in [
+            (0, Severity::Consider),
+            (2, Severity::MustFix),
+            (4, Severity::MustFix),
+        ] {
+            let mut ann = Annotation::new(
+                TextRange::new(start, start + 1),
+                String::new(),
+                String::new(),
+            );
+            ann.severity = severity;
+            doc.add_annotation(ann);
+        }
+
+        let mut app = App::new();
+        app.load_document(doc);
+        app.sort_order = SortOrder::Severity;
+        app.group_annotations = true;
+
+        let rows = app.sidebar_rows();
+        assert_eq!(rows.len(), 5);
+        assert!(matches!(&rows[0], SidebarRow::Header { count: 2, .. }));
+
+        // Collapsing the MustFix group leaves only the Consider annotation selectable
+        app.collapse_selected_group();
+        assert_eq!(app.visible_annotations().len(), 1);
+        assert_eq!(
+            app.selected_annotation().map(|a| a.range.start_offset),
+            Some(0)
+        );
+
+        let items = app
+            .sidebar_rows()
+            .iter()
+            .filter(|r| matches!(r, SidebarRow::Item { .. }))
+            .count();
+        assert_eq!(items, app.visible_annotations().len());
+    }
+
+    #[test]
+    fn test_inline_comment_anchors() {
+        let content = "one\ntwo\n\nthree";
+        let mut doc = Document::new("Test".to_string(), content.to_string());
+        doc.add_annotation(Annotation::new(
+            TextRange::new(0, 2),
+            "on".to_string(),
+            "c".to_string(),
+        ));
+
+        let mut app = App::new();
+        app.load_document(doc);
+        assert!(app.inline_comment_anchors().is_empty());
+
+        app.inline_comments = InlineComments::EndOfLine;
+        assert_eq!(app.inline_comment_anchors()[0].0, 0);
+
+        // Below the paragraph: after "two", before the blank line
+        app.inline_comments = InlineComments::Below;
+        assert_eq!(app.inline_comment_anchors()[0].0, 1);
+    }
+
+    #[test]
+    fn test_apply_suggestion_shifts_ranges() {
+        let mut doc = Document::new("Test".to_string(), "very big dog\nend".to_string());
+        doc.add_annotation(Annotation::new(
+            TextRange::new(0, 8),
+            "very big".to_string(),
+            "Wordy".to_string(),
+        ));
+        doc.add_annotation(Annotation::new(
+            TextRange::new(9, 12),
+            "dog".to_string(),
+            "Which?".to_string(),
+        ));
+
+        let mut app = App::new();
+        app.load_document(doc);
+        assert!(!app.apply_suggestion());
+        app.set_suggestion(Some("huge".to_string()));
+        assert_eq!(app.suggestion_anchors()[0].0, 0);
+        assert!(app.apply_suggestion());
+
+        let doc = app.document.as_ref().unwrap();
+        assert_eq!(doc.content, "huge dog\nend");
+        assert!(doc.annotations[0].is_resolved && doc.annotations[0].suggestion.is_none());
+        assert_eq!(doc.annotations[1].range, TextRange::new(5, 8));
+        assert!(app.content_modified);
+        assert!(app.suggestion_anchors().is_empty());
+
+        // Quitting or opening another file now needs :w or :q!
+        assert!(app.refuse_unsaved());
+        assert_eq!(app.status_message.as_deref(), Some(UNSAVED_CHANGES));
+    }
+
+    #[test]
+    fn test_point_annotation() {
+        let mut doc = Document::new("Test".to_string(), "one two\nthree".to_string());
+        doc.add_annotation(Annotation::new(
+            TextRange::new(4, 7),
+            "two".to_string(),
+            "Vague".to_string(),
+        ));
+
+        let mut app = App::new();
+        app.load_document(doc);
+        app.move_word_forward();
+        app.enter_visual_mode();
+        app.input_buffer = "Add a transition".to_string();
+        app.start_annotation();
+        assert_eq!(app.mode, Mode::SeverityPicker);
+        assert!(app.complete_annotation());
+
+        let doc = app.document.as_ref().unwrap();
+        let sorted: Vec<_> = doc
+            .annotations_sorted()
+            .iter()
+            .map(|a| a.comment.as_str())
+            .collect();
+        assert_eq!(sorted, vec!["Add a transition", "Vague"]);
+        assert_eq!(doc.insertion_points()[0].range, TextRange::new(4, 4));
+        assert_eq!(app.annotations_at_cursor().len(), 2);
+
+        let prompt = crate::export::generate_prompt(doc);
+        assert!(prompt.contains(
+            "**Insert at line 1, after \"one\" and before \"two\"**\n- Feedback: Add a transition"
+        ));
+        let json = crate::export::to_json(doc).unwrap();
+        assert!(json.contains(
+            "\"insertAt\": \"Insert at line 1, after \\\"one\\\" and before \\\"two\\\"\""
+        ));
+
+        // Deleting nothing at a point isn't shown as a suggestion
+        app.document.as_mut().unwrap().annotations[1].suggestion = Some(String::new());
+        assert!(app.suggestion_anchors().is_empty());
+
+        // Replacing text around a point leaves it a point
+        let doc = app.document.as_mut().unwrap();
+        doc.replace_range(&TextRange::new(0, 7), "1 2");
+        assert_eq!(doc.insertion_points()[0].range, TextRange::new(0, 0));
+    }
+
+    #[test]
+    fn test_general_comments() {
+        let mut doc = Document::new(
+            "Test".to_string(),
+            "# One\nfirst\n## Two\nsecond".to_string(),
+        );
+        doc.add_annotation(Annotation::new(
+            TextRange::new(6, 11),
+            "first".to_string(),
+            "Vague".to_string(),
+        ));
+
+        let mut app = App::new();
+        app.load_document(doc);
+        app.move_to_bottom();
+        app.start_general_comment(Scope::Section);
+        app.input_buffer = "Should come first".to_string();
+        assert!(app.complete_annotation());
+        app.start_general_comment(Scope::Document);
+        app.input_buffer = "Too long".to_string();
+        assert!(app.complete_annotation());
+
+        let comments: Vec<_> = app
+            .visible_annotations()
+            .iter()
+            .map(|a| a.comment.as_str())
+            .collect();
+        assert_eq!(comments, vec!["Too long", "Should come first", "Vague"]);
+        let doc = app.document.as_ref().unwrap();
+        assert_eq!(doc.annotations[1].selected_text, "## Two");
+        assert_eq!(doc.coverage().len(), 1);
+        assert!(doc.insertion_points().is_empty());
+        assert_eq!(
+            app.line_markers(),
+            vec![None, Some(Severity::ShouldFix), None, None]
+        );
+
+        let prompt = crate::export::generate_prompt(doc);
+        assert!(prompt.contains(
+            "### General Comments (2 items)\n\n**Whole document** (Should Fix)\n- Feedback: Too long\n\n\
+             **Section \"Two\"** (Should Fix)\n- Feedback: Should come first\n\n---\n\n### Annotations (1 items)"
+        ));
+
+        app.select_annotation(0);
+        app.start_suggestion();
+        assert_eq!(app.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn test_line_markers() {
+        let content = "one\ntwo\nthree";
+        let mut doc = Document::new("Test".to_string(), content.to_string());
+        let mut must = Annotation::new(TextRange::new(5, 6), "w".to_string(), String::new());
+        must.severity = Severity::MustFix;
+        doc.add_annotation(Annotation::new(
+            TextRange::new(2, 6),
+            "e\ntw".to_string(),
+            String::new(),
+        ));
+        doc.add_annotation(must);
+
+        let mut app = App::new();
+        app.load_document(doc);
+        assert_eq!(
+            app.line_markers(),
+            vec![Some(Severity::ShouldFix), Some(Severity::MustFix), None]
+        );
+
+        app.gutter = GutterMode::Relative;
+        app.move_down();
+        assert_eq!(app.line_number_label(1), Some(2));
+        assert_eq!(app.line_number_label(2), Some(1));
+    }
+}
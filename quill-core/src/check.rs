@@ -0,0 +1,259 @@
+//! Pass/fail review gate over unresolved annotations
+
+use serde::Serialize;
+
+use crate::export::ExportAnnotation;
+use crate::model::{Document, Severity};
+use crate::outline::SeverityCounts;
+
+/// Most unresolved annotations allowed per severity; `None` means no limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub must_fix: Option<usize>,
+    pub should_fix: Option<usize>,
+    pub consider: Option<usize>,
+}
+
+impl Default for Thresholds {
+    /// Fail on any must-fix annotation
+    fn default() -> Self {
+        Self {
+            must_fix: Some(0),
+            should_fix: None,
+            consider: None,
+        }
+    }
+}
+
+impl Thresholds {
+    pub fn limit(&self, severity: Severity) -> Option<usize> {
+        match severity {
+            Severity::MustFix => self.must_fix,
+            Severity::ShouldFix => self.should_fix,
+            Severity::Consider => self.consider,
+        }
+    }
+}
+
+/// Result of checking one document against thresholds
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckReport {
+    pub title: String,
+    pub filepath: Option<String>,
+    pub passed: bool,
+    pub counts: SeverityCounts,
+    /// One message per severity over its limit
+    pub violations: Vec<String>,
+    /// Unresolved annotations, grouped by severity
+    pub annotations: Vec<CheckedAnnotation>,
+    /// Unresolved annotations whose text is no longer in the document; they
+    /// still count against their severity's limit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub orphaned: Vec<ExportAnnotation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckedAnnotation {
+    pub line: usize,
+    #[serde(flatten)]
+    pub annotation: ExportAnnotation,
+}
+
+/// Count unresolved annotations and compare them against `thresholds`
+pub fn check(doc: &Document, thresholds: &Thresholds) -> CheckReport {
+    let mut counts = SeverityCounts::default();
+    let mut annotations = Vec::new();
+
+    for severity in Severity::all() {
+        let mut items: Vec<_> = doc
+            .annotations
+            .iter()
+            .filter(|a| !a.is_resolved && a.severity == *severity)
+            .collect();
+        items.sort_by_key(|a| a.range.start_offset);
+
+        // Annotations whose text is gone still count against their limit
+        let count = items.len()
+            + doc
+                .orphaned
+                .iter()
+                .filter(|a| !a.is_resolved && a.severity == *severity)
+                .count();
+        match severity {
+            Severity::MustFix => counts.must_fix = count,
+            Severity::ShouldFix => counts.should_fix = count,
+            Severity::Consider => counts.consider = count,
+        }
+        annotations.extend(items.into_iter().map(|a| CheckedAnnotation {
+            line: doc.line_number(a.range.start_offset),
+            annotation: ExportAnnotation::new(doc, a),
+        }));
+    }
+
+    let orphaned: Vec<ExportAnnotation> = doc
+        .orphaned
+        .iter()
+        .filter(|a| !a.is_resolved)
+        .map(ExportAnnotation::from)
+        .collect();
+
+    let violations: Vec<String> = Severity::all()
+        .iter()
+        .filter_map(|&severity| {
+            let limit = thresholds.limit(severity)?;
+            let count = counts.get(severity);
+            (count > limit)
+                .then(|| format!("{} {} unresolved (limit {})", count, severity.name(), limit))
+        })
+        .collect();
+
+    CheckReport {
+        title: doc.title.clone(),
+        filepath: doc.filepath.clone(),
+        passed: violations.is_empty(),
+        counts,
+        violations,
+        annotations,
+        orphaned,
+    }
+}
+
+impl CheckReport {
+    /// Compact plain-text report, one line per unresolved annotation
+    pub fn to_text(&self) -> String {
+        let name = self.filepath.as_deref().unwrap_or(&self.title);
+        let mut out = format!(
+            "{}: {} ({} must-fix, {} should-fix, {} consider unresolved)\n",
+            name,
+            if self.passed { "ok" } else { "FAILED" },
+            self.counts.must_fix,
+            self.counts.should_fix,
+            self.counts.consider
+        );
+        for violation in &self.violations {
+            out.push_str(&format!("  {}\n", violation));
+        }
+
+        for severity in Severity::all() {
+            let items: Vec<_> = self
+                .annotations
+                .iter()
+                .filter(|a| a.annotation.severity == severity.name())
+                .collect();
+            if items.is_empty() {
+                continue;
+            }
+
+            out.push_str(&format!("\n  {} ({})\n", severity.as_str(), items.len()));
+            for item in items {
+                let ann = &item.annotation;
+                out.push_str(&format!(
+                    "    L{:<4} \"{}\" {}\n",
+                    item.line,
+                    ann.text.replace('\n', " "),
+                    ann.comment.replace('\n', " ")
+                ));
+            }
+        }
+
+        if !self.orphaned.is_empty() {
+            out.push_str(&format!(
+                "\n  No longer in the text ({})\n",
+                self.orphaned.len()
+            ));
+            for ann in &self.orphaned {
+                out.push_str(&format!(
+                    "    {:<10} \"{}\" {}\n",
+                    ann.severity,
+                    ann.text.replace('\n', " "),
+                    ann.comment.replace('\n', " ")
+                ));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Annotation, TextRange};
+    use crate::sidecar::{apply_sidecar, to_sidecar_json};
+
+    #[test]
+    fn test_check_thresholds() {
+        let mut doc = Document::new("Test".to_string(), "one\ntwo\nthree".to_string());
+        for (start, end, severity) in [
+            (0, 3, Severity::ShouldFix),
+            (4, 7, Severity::ShouldFix),
+            (8, 13, Severity::MustFix),
+        ] {
+            let text = doc.content[start..end].to_string();
+            let mut ann = Annotation::new(TextRange::new(start, end), text, "fix".to_string());
+            ann.severity = severity;
+            doc.add_annotation(ann);
+        }
+
+        let report = check(&doc, &Thresholds::default());
+        assert!(!report.passed);
+        assert_eq!(report.violations, vec!["1 must-fix unresolved (limit 0)"]);
+        assert_eq!(report.annotations[0].line, 3);
+        assert!(report.to_text().contains("L1    \"one\" fix"));
+
+        doc.annotations
+            .iter_mut()
+            .find(|a| a.severity == Severity::MustFix)
+            .unwrap()
+            .is_resolved = true;
+        assert!(check(&doc, &Thresholds::default()).passed);
+
+        let strict = Thresholds {
+            should_fix: Some(1),
+            ..Thresholds::default()
+        };
+        let report = check(&doc, &strict);
+        assert_eq!(report.violations, vec!["2 should-fix unresolved (limit 1)"]);
+    }
+
+    #[test]
+    fn test_check_counts_annotations_after_text_changed() {
+        let mut doc = Document::new("Test".to_string(), "Hello world".to_string());
+        let mut ann = Annotation::new(
+            TextRange::new(6, 11),
+            "world".to_string(),
+            "Vague".to_string(),
+        );
+        ann.severity = Severity::MustFix;
+        doc.add_annotation(ann);
+        let json = to_sidecar_json(&doc).unwrap();
+
+        // Moved text is still found and checked
+        let mut moved = Document::new("Test".to_string(), "Hi world".to_string());
+        apply_sidecar(&mut moved, &json).unwrap();
+        let report = check(&moved, &Thresholds::default());
+        assert_eq!(report.counts.must_fix, 1);
+        assert!(!report.passed);
+
+        // Removed text is listed and still counts against its severity's limit
+        let mut edited = Document::new("Test".to_string(), "Hi earth".to_string());
+        apply_sidecar(&mut edited, &json).unwrap();
+        let report = check(&edited, &Thresholds::default());
+        assert!(!report.passed);
+        assert_eq!(report.counts.must_fix, 1);
+        assert_eq!(report.violations, vec!["1 must-fix unresolved (limit 0)"]);
+        assert_eq!(report.orphaned.len(), 1);
+        assert!(report.to_text().contains("must-fix   \"world\" Vague"));
+
+        // An orphan whose severity has no limit is reported but doesn't fail
+        edited.orphaned[0].severity = Severity::Consider;
+        let report = check(&edited, &Thresholds::default());
+        assert!(report.passed);
+        assert_eq!(report.counts.consider, 1);
+        assert_eq!(report.orphaned.len(), 1);
+
+        edited.orphaned[0].is_resolved = true;
+        assert_eq!(check(&edited, &Thresholds::default()).counts.consider, 0);
+    }
+}
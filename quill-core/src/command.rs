@@ -0,0 +1,495 @@
+use crate::app::{App, GutterMode, InlineComments, InputTarget, Mode};
+use crate::dispatch::{EXPORT_FILENAME, PROMPT_FILENAME};
+use crate::export::to_json;
+use crate::filter::AnnotationFilter;
+use crate::keymap::Action;
+use crate::model::{Category, Severity};
+use crate::platform::Platform;
+use crate::sidecar::orphaned_warning;
+use crate::sort::SortOrder;
+
+/// Commands accepted at the `:` prompt, besides every action name
+pub const COMMANDS: &[(&str, &str)] = &[
+    ("w", "Save annotations next to the document"),
+    ("q", "Quit"),
+    ("q!", "Quit, discarding edited text"),
+    ("wq", "Save annotations and quit"),
+    ("e", "Open a file: e PATH"),
+    ("e!", "Open a file, discarding edited text: e! PATH"),
+    ("response", "Review revisions from a model response: response PATH"),
+    ("send", "Pipe the prompt to a command: send [COMMAND]"),
+    ("diff", "Compare with a revised version: diff PATH"),
+    ("export", "Export as json or md"),
+    ("filter", "Filter: severity= category= resolved=hide text=, or clear"),
+    ("resolve-all", "Resolve annotations matching severity= category= text="),
+    ("goto", "Go to a line number"),
+    ("set", "Set options: theme= template= sort= gutter= inline= group= fulltext= excerpts= budget= structured= sendformat="),
+];
+
+/// Options accepted by `:set`
+const OPTIONS: &[&str] = &[
+    "theme",
+    "template",
+    "sort",
+    "gutter",
+    "inline",
+    "group",
+    "fulltext",
+    "excerpts",
+    "budget",
+    "structured",
+    "sendformat",
+];
+
+/// Criteria accepted by `:filter` and `:resolve-all`
+const FILTER_KEYS: &[&str] = &["severity", "category", "resolved", "text"];
+
+impl App {
+    /// Open the `:` command prompt
+    pub fn start_command(&mut self) {
+        self.input_buffer.clear();
+        self.completions.clear();
+        self.input_target = InputTarget::Command;
+        self.mode = Mode::Input;
+    }
+
+    /// Run a command line (without the leading `:`), reporting errors in the status bar
+    pub fn run_command(&mut self, line: &str, platform: &mut dyn Platform) {
+        if let Err(e) = self.execute_command(line, platform) {
+            self.set_status(&format!("Error: {}", e));
+        }
+    }
+
+    fn execute_command(&mut self, line: &str, platform: &mut dyn Platform) -> Result<(), String> {
+        let line = line.trim();
+        let (name, args) = match line.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args.trim()),
+            None => (line, ""),
+        };
+
+        match name {
+            "" => {}
+            "w" | "write" => self.write_sidecar(platform)?,
+            "q" | "quit" => {
+                if !self.refuse_unsaved() {
+                    self.running = false;
+                }
+            }
+            "q!" => self.running = false,
+            "wq" => {
+                self.write_sidecar(platform)?;
+                self.running = false;
+            }
+            "e" | "edit" | "e!" => {
+                if name != "e!" && self.refuse_unsaved() {
+                    return Ok(());
+                }
+                if args.is_empty() {
+                    if !platform.pick_file() {
+                        return Err("Usage: e PATH".to_string());
+                    }
+                } else {
+                    let doc = platform.open_file(args).map_err(|e| e.to_string())?;
+                    self.open_document(doc, args);
+                }
+            }
+            "response" => {
+                if self.document.is_none() {
+                    return Err("No document loaded".to_string());
+                }
+                if args.is_empty() {
+                    if !platform.pick_response() {
+                        return Err("Usage: response PATH".to_string());
+                    }
+                } else {
+                    let text = platform.read_text(args).map_err(|e| e.to_string())?;
+                    self.load_response(&text);
+                }
+            }
+            "diff" => {
+                if self.document.is_none() {
+                    return Err("No document loaded".to_string());
+                }
+                if args.is_empty() {
+                    if !platform.pick_revision() {
+                        return Err("Usage: diff PATH".to_string());
+                    }
+                } else {
+                    let text = platform.read_text(args).map_err(|e| e.to_string())?;
+                    self.load_revision(args, text);
+                }
+            }
+            "send" => self.start_send(Some(args).filter(|a| !a.is_empty()), platform),
+            "export" => self.export_as(args, platform)?,
+            "filter" => {
+                let filter = parse_filter(args)?;
+                self.set_filter(filter);
+            }
+            "resolve-all" => {
+                let filter = parse_filter(args)?;
+                let count = self.resolve_matching(&filter);
+                self.set_status(&format!("Resolved {} annotations", count));
+            }
+            "goto" => {
+                let line = args
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid line number: {}", args))?;
+                self.goto_line(line);
+            }
+            "set" => {
+                if args.is_empty() {
+                    return Err("Usage: set OPTION=VALUE".to_string());
+                }
+                for assignment in args.split_whitespace() {
+                    let (key, value) = assignment
+                        .split_once('=')
+                        .ok_or_else(|| format!("Expected OPTION=VALUE, got {}", assignment))?;
+                    self.set_option(key, value)?;
+                }
+            }
+            _ => {
+                let action: Action = name
+                    .parse()
+                    .map_err(|_| format!("Unknown command: {}", name))?;
+                self.dispatch(action, platform);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sidecar(&mut self, platform: &mut dyn Platform) -> Result<(), String> {
+        let doc = self.document.as_ref().ok_or("No document loaded")?;
+        // Accepted revisions change the text, which the annotations now refer to
+        let mut saved = Vec::new();
+        if self.content_modified {
+            saved.push(platform.save_document(doc).map_err(|e| e.to_string())?);
+            self.content_modified = false;
+        }
+        saved.push(platform.save_sidecar(doc).map_err(|e| e.to_string())?);
+        let mut status = format!("Saved {}", saved.join(" and "));
+        if let Some(warning) = orphaned_warning(doc) {
+            status = format!("{}; {}", status, warning);
+        }
+        self.set_status(&status);
+        Ok(())
+    }
+
+    fn export_as(&mut self, format: &str, platform: &mut dyn Platform) -> Result<(), String> {
+        let doc = self.export_document().ok_or("No document loaded")?;
+        let (filename, contents) = match format {
+            "" | "json" => (
+                EXPORT_FILENAME.to_string(),
+                to_json(&doc).map_err(|e| e.to_string())?,
+            ),
+            "md" => (
+                PROMPT_FILENAME.to_string(),
+                self.template.render(&doc, &self.prompt_options),
+            ),
+            _ => return Err(format!("Unknown export format: {}", format)),
+        };
+        let location = platform
+            .save_export(&filename, &contents)
+            .map_err(|e| e.to_string())?;
+        self.set_status(&format!("Exported to {}", location));
+        Ok(())
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "theme" => {
+                if !self.set_theme(value) {
+                    return Err(format!("Unknown theme: {}", value));
+                }
+            }
+            "template" => {
+                if !self.set_template(value) {
+                    return Err(format!("Unknown template: {}", value));
+                }
+            }
+            "sort" => {
+                let order = SortOrder::all()
+                    .iter()
+                    .find(|o| o.as_str().eq_ignore_ascii_case(value))
+                    .ok_or_else(|| format!("Unknown sort order: {}", value))?;
+                self.set_sort_order(*order);
+            }
+            "gutter" => {
+                self.gutter = match value {
+                    "off" => GutterMode::Off,
+                    "absolute" => GutterMode::Absolute,
+                    "relative" => GutterMode::Relative,
+                    _ => return Err(format!("Unknown gutter mode: {}", value)),
+                };
+            }
+            "inline" => {
+                self.inline_comments = match value {
+                    "off" => InlineComments::Off,
+                    "eol" => InlineComments::EndOfLine,
+                    "below" => InlineComments::Below,
+                    _ => return Err(format!("Unknown inline comment mode: {}", value)),
+                };
+            }
+            "group" => {
+                let group = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("Expected on or off, got {}", value)),
+                };
+                if group != self.group_annotations {
+                    self.toggle_grouping();
+                }
+            }
+            "fulltext" => {
+                self.prompt_options.full_text = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("Expected on or off, got {}", value)),
+                };
+            }
+            "excerpts" => {
+                self.prompt_options.excerpts = match value {
+                    "off" => None,
+                    _ => Some(value.parse()?),
+                };
+            }
+            "structured" => {
+                self.prompt_options.structured = match value {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("Expected on or off, got {}", value)),
+                };
+            }
+            "sendformat" => self.send.format = value.parse()?,
+            "budget" => {
+                self.prompt_options.token_budget =
+                    match value {
+                        "off" => None,
+                        _ => Some(value.parse().map_err(|_| {
+                            format!("Expected a token count or off, got {}", value)
+                        })?),
+                    };
+            }
+            _ => return Err(format!("Unknown option: {}", key)),
+        }
+        self.set_status(&format!("{}={}", key, value));
+        Ok(())
+    }
+
+    fn option_values(&self, key: &str) -> Vec<String> {
+        match key {
+            "theme" => self.themes.iter().map(|t| t.name.clone()).collect(),
+            "template" => self.templates.iter().map(|t| t.name.clone()).collect(),
+            "sort" => SortOrder::all()
+                .iter()
+                .map(|o| o.as_str().to_lowercase())
+                .collect(),
+            "gutter" => to_strings(&["off", "absolute", "relative"]),
+            "inline" => to_strings(&["off", "eol", "below"]),
+            "group" | "fulltext" | "structured" => to_strings(&["on", "off"]),
+            "excerpts" => to_strings(&["off", "0p", "1p", "1s", "2s"]),
+            "sendformat" => to_strings(&["prompt", "json"]),
+            "budget" => to_strings(&["off", "8000", "32000", "100000"]),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Candidate completions for a partial command line, as whole lines
+    pub fn command_completions(&self, line: &str) -> Vec<String> {
+        let Some((name, args)) = line.split_once(' ') else {
+            let mut names: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).collect();
+            names.extend(Action::all().iter().map(|a| a.name()));
+            return names
+                .into_iter()
+                .filter(|n| n.starts_with(line))
+                .map(String::from)
+                .collect();
+        };
+
+        // Complete the last word of the arguments
+        let (head, last) = match args.rfind(' ') {
+            Some(i) => args.split_at(i + 1),
+            None => ("", args),
+        };
+        let candidates = match (name, last.split_once('=')) {
+            ("set", None) => OPTIONS.iter().map(|o| format!("{}=", o)).collect(),
+            ("set", Some((key, _))) => prefixed(key, self.option_values(key)),
+            ("filter" | "resolve-all", None) => {
+                FILTER_KEYS.iter().map(|k| format!("{}=", k)).collect()
+            }
+            ("filter" | "resolve-all", Some((key, _))) => prefixed(key, filter_values(key)),
+            ("export", None) => to_strings(&["json", "md"]),
+            _ => Vec::new(),
+        };
+        candidates
+            .into_iter()
+            .filter(|c| c.starts_with(last))
+            .map(|c| format!("{} {}{}", name, head, c))
+            .collect()
+    }
+
+    /// Complete the command line in place, cycling through candidates on repeated calls
+    pub fn complete_command(&mut self) {
+        if self.completions.is_empty() {
+            self.completions = self.command_completions(&self.input_buffer);
+            self.completion_index = 0;
+        } else {
+            self.completion_index = (self.completion_index + 1) % self.completions.len();
+        }
+
+        match self.completions.len() {
+            0 => {}
+            1 => {
+                // Accept a lone candidate so the next Tab completes what follows it
+                self.input_buffer = self.completions.remove(0);
+            }
+            _ => {
+                self.input_buffer = self.completions[self.completion_index].clone();
+                let options: Vec<&str> = self
+                    .completions
+                    .iter()
+                    .map(|c| c.rsplit([' ', '=']).next().unwrap_or(c))
+                    .collect();
+                self.set_status(&options.join("  "));
+            }
+        }
+    }
+}
+
+/// Parse `key=value` criteria into a filter; empty or `clear` gives no criteria
+fn parse_filter(args: &str) -> Result<AnnotationFilter, String> {
+    let mut filter = AnnotationFilter::default();
+    if args.is_empty() || args == "clear" {
+        return Ok(filter);
+    }
+
+    let mut rest = args;
+    while !rest.is_empty() {
+        let (key, value) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("Expected KEY=VALUE, got {}", rest))?;
+        // Text takes the remainder of the line so it can contain spaces
+        let value = if key == "text" {
+            let value = rest[key.len() + 1..].to_string();
+            rest = "";
+            value
+        } else {
+            let (value, tail) = value.split_once(' ').unwrap_or((value, ""));
+            rest = tail.trim_start();
+            value.to_string()
+        };
+
+        match key {
+            "severity" => filter.severity = Some(value.parse::<Severity>()?),
+            "category" => filter.category = Some(value.parse::<Category>()?),
+            "resolved" => {
+                filter.hide_resolved = match value.as_str() {
+                    "hide" => true,
+                    "show" => false,
+                    _ => return Err(format!("Expected hide or show, got {}", value)),
+                }
+            }
+            "text" => filter.query = value,
+            _ => return Err(format!("Unknown filter: {}", key)),
+        }
+    }
+    Ok(filter)
+}
+
+fn filter_values(key: &str) -> Vec<String> {
+    match key {
+        "severity" => Severity::all()
+            .iter()
+            .map(|s| s.name().to_string())
+            .collect(),
+        "category" => Category::all()
+            .iter()
+            .map(|c| c.as_str().to_string())
+            .collect(),
+        "resolved" => to_strings(&["hide", "show"]),
+        _ => Vec::new(),
+    }
+}
+
+fn prefixed(key: &str, values: Vec<String>) -> Vec<String> {
+    values
+        .into_iter()
+        .map(|v| format!("{}={}", key, v))
+        .collect()
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Annotation, Document, TextRange};
+
+    fn app_with_annotations() -> App {
+        let mut app = App::new();
+        let mut doc = Document::new("Test".to_string(), "one\ntwo\nthree\n".to_string());
+        for (start, severity, category) in [
+            (0, Severity::MustFix, Category::Voice),
+            (4, Severity::Consider, Category::Voice),
+            (8, Severity::MustFix, Category::Clarity),
+        ] {
+            let mut ann = Annotation::new(
+                TextRange::new(start, start + 3),
+                String::new(),
+                String::new(),
+            );
+            ann.severity = severity;
+            ann.category = Some(category);
+            doc.add_annotation(ann);
+        }
+        app.load_document(doc);
+        app
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = parse_filter("severity=must-fix category=voice text=too long").unwrap();
+        assert_eq!(filter.severity, Some(Severity::MustFix));
+        assert_eq!(filter.category, Some(Category::Voice));
+        assert_eq!(filter.query, "too long");
+        assert!(parse_filter("severity=urgent").is_err());
+        assert!(!parse_filter("clear").unwrap().is_active());
+    }
+
+    #[test]
+    fn test_resolve_all_and_goto() {
+        let mut app = app_with_annotations();
+        let filter = parse_filter("category=Voice").unwrap();
+        assert_eq!(app.resolve_matching(&filter), 2);
+        let resolved: Vec<_> = app
+            .document
+            .as_ref()
+            .unwrap()
+            .annotations
+            .iter()
+            .map(|a| a.is_resolved)
+            .collect();
+        assert_eq!(resolved, vec![true, true, false]);
+
+        app.goto_line(3);
+        assert_eq!(app.cursor_pos(), (2, 0));
+    }
+
+    #[test]
+    fn test_completions() {
+        let app = app_with_annotations();
+        assert_eq!(app.command_completions("wq"), vec!["wq".to_string()]);
+        assert!(app
+            .command_completions("next-")
+            .contains(&"next-annotation".to_string()));
+        assert_eq!(
+            app.command_completions("set theme=la"),
+            vec!["set theme=latte".to_string()]
+        );
+        assert_eq!(
+            app.command_completions("filter severity=must-fix cat"),
+            vec!["filter severity=must-fix category=".to_string()]
+        );
+    }
+}
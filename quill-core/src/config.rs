@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::keymap::KeyOverrides;
+use crate::send::SendConfig;
+use crate::theme::ThemeSpec;
+
+/// User configuration, read by each frontend from its own storage
+/// (`~/.quill/config.toml` on the CLI, localStorage on the web)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub keys: KeyOverrides,
+    /// Theme to start with
+    pub theme: Option<String>,
+    /// User-defined themes by name
+    #[serde(default)]
+    pub themes: BTreeMap<String, ThemeSpec>,
+    /// Prompt template to start with
+    pub template: Option<String>,
+    /// Prompt template sources by name
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+    /// External command for the send action
+    #[serde(default)]
+    pub send: SendConfig,
+}
+
+impl Config {
+    /// Parse configuration stored as JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::theme::ThemeColor;
+
+    #[test]
+    fn test_config_from_json() {
+        let config = Config::from_json(r#"{"keys": {"normal": {"ctrl+s": "export"}}}"#).unwrap();
+        assert_eq!(
+            config.keys.normal.get("ctrl+s").map(String::as_str),
+            Some("export")
+        );
+        assert!(config.keys.visual.is_empty());
+
+        assert!(Config::from_json("{}").unwrap().keys.normal.is_empty());
+    }
+
+    #[test]
+    fn test_config_selects_user_theme() {
+        let config = Config::from_json(
+            r##"{"theme": "mine", "themes": {"mine": {"extends": "latte", "red": "#ff0000"}}}"##,
+        )
+        .unwrap();
+        let mut app = App::new();
+        assert!(app.apply_config(&config).is_empty());
+        assert_eq!(app.theme.name, "mine");
+        assert_eq!(app.theme.red, ThemeColor::Rgb(255, 0, 0));
+
+        let config = Config::from_json(r#"{"theme": "solarized"}"#).unwrap();
+        assert_eq!(
+            app.apply_config(&config),
+            vec!["Unknown theme: solarized".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_config_selects_user_template() {
+        let config = Config::from_json(
+            r#"{"template": "diff", "templates": {"diff": "Reply with a diff of {{title}}"}}"#,
+        )
+        .unwrap();
+        let mut app = App::new();
+        assert!(app.apply_config(&config).is_empty());
+        app.load_document(crate::model::Document::new(
+            "Notes".to_string(),
+            "Text".to_string(),
+        ));
+        assert_eq!(
+            app.prompt_text().as_deref(),
+            Some("Reply with a diff of Notes")
+        );
+
+        let config = Config::from_json(r#"{"templates": {"bad": "{{#open}}"}}"#).unwrap();
+        assert_eq!(
+            app.apply_config(&config),
+            vec!["Template bad: Unclosed section {{#open}}".to_string()]
+        );
+    }
+}
@@ -69,6 +69,38 @@ impl CursorState {
         self.col = col;
     }
 
+    /// Set cursor to (row, col), clamped to the content
+    pub fn set_position(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.lines.len().saturating_sub(1));
+        let line_len = self
+            .lines
+            .get(self.row)
+            .map(|l| l.chars().count())
+            .unwrap_or(0);
+        self.col = col.min(line_len);
+    }
+
+    /// Column range (start, end) of the word at (row, col), empty if not on a word
+    pub fn word_bounds(&self, row: usize, col: usize) -> (usize, usize) {
+        let chars: Vec<char> = match self.lines.get(row) {
+            Some(line) => line.chars().collect(),
+            None => return (col, col),
+        };
+        if chars.get(col).is_none_or(|c| c.is_whitespace()) {
+            return (col, col);
+        }
+
+        let mut start = col;
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        let mut end = col;
+        while end < chars.len() && !chars[end].is_whitespace() {
+            end += 1;
+        }
+        (start, end)
+    }
+
     /// Get the current line content
     pub fn current_line(&self) -> Option<&str> {
         self.lines.get(self.row).map(|s| s.as_str())
@@ -241,4 +273,16 @@ mod tests {
         assert_eq!(cursor.offset_to_cursor(6), (1, 0));
         assert_eq!(cursor.offset_to_cursor(8), (1, 2));
     }
+
+    #[test]
+    fn test_word_bounds_and_set_position() {
+        let mut cursor = CursorState::new();
+        cursor.set_content("Hello brave\nnew world");
+
+        assert_eq!(cursor.word_bounds(0, 7), (6, 11));
+        assert_eq!(cursor.word_bounds(0, 5), (5, 5));
+
+        cursor.set_position(5, 99);
+        assert_eq!(cursor.cursor(), (1, 9));
+    }
 }
@@ -0,0 +1,357 @@
+//! Word-level diff between a document and a revised version, reviewed hunk
+//! by hunk and merged back into the document
+
+use std::ops::Range;
+
+use similar::{DiffTag, TextDiff};
+use uuid::Uuid;
+
+use crate::app::{App, Mode};
+use crate::keymap::Key;
+use crate::model::{Document, TextRange};
+use crate::platform::Platform;
+use crate::response::ProposalStatus;
+
+/// One change between the original and revised text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Byte range replaced in the original
+    pub original: Range<usize>,
+    /// Byte range of the replacement in the revised text
+    pub revised: Range<usize>,
+    pub status: ProposalStatus,
+    /// Annotations whose ranges the hunk touches
+    pub annotations: Vec<Uuid>,
+}
+
+/// A piece of the combined diff text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    Equal(&'a str),
+    Removed(&'a str, usize),
+    Inserted(&'a str, usize),
+}
+
+/// A revised version of the document being compared with it
+#[derive(Debug, Clone, Default)]
+pub struct Revision {
+    /// Where the revised text came from
+    pub name: String,
+    pub text: String,
+    pub hunks: Vec<Hunk>,
+    pub selected: usize,
+}
+
+impl Revision {
+    /// Diff `text` against the document's content
+    pub fn new(name: &str, doc: &Document, text: String) -> Self {
+        let hunks = diff_words(&doc.content, &text)
+            .into_iter()
+            .map(|(original, revised)| Hunk {
+                annotations: doc
+                    .annotations
+                    .iter()
+                    .filter(|a| !a.is_general() && touches(&a.range, &original))
+                    .map(|a| a.id)
+                    .collect(),
+                original,
+                revised,
+                status: ProposalStatus::Pending,
+            })
+            .collect();
+        Self {
+            name: name.to_string(),
+            text,
+            hunks,
+            selected: 0,
+        }
+    }
+
+    pub fn count(&self, status: ProposalStatus) -> usize {
+        self.hunks.iter().filter(|h| h.status == status).count()
+    }
+
+    /// The original text interleaved with each hunk's removed and inserted text
+    pub fn segments<'a>(&'a self, original: &'a str) -> Vec<Segment<'a>> {
+        let mut segments = Vec::new();
+        let mut offset = 0;
+        for (i, hunk) in self.hunks.iter().enumerate() {
+            if hunk.original.start > offset {
+                segments.push(Segment::Equal(&original[offset..hunk.original.start]));
+            }
+            if !hunk.original.is_empty() {
+                segments.push(Segment::Removed(&original[hunk.original.clone()], i));
+            }
+            if !hunk.revised.is_empty() {
+                segments.push(Segment::Inserted(&self.text[hunk.revised.clone()], i));
+            }
+            offset = hunk.original.end;
+        }
+        if offset < original.len() {
+            segments.push(Segment::Equal(&original[offset..]));
+        }
+        segments
+    }
+
+    /// Line of the combined diff text on which a hunk starts
+    pub fn hunk_line(&self, original: &str, index: usize) -> usize {
+        let Some(hunk) = self.hunks.get(index) else {
+            return 0;
+        };
+        let inserted: usize = self.hunks[..index]
+            .iter()
+            .map(|h| self.text[h.revised.clone()].matches('\n').count())
+            .sum();
+        original[..hunk.original.start].matches('\n').count() + inserted
+    }
+
+    /// Apply the accepted hunks to the document, resolving the annotations
+    /// they touch. Returns how many hunks were applied.
+    pub fn apply_accepted(&self, doc: &mut Document) -> usize {
+        let mut applied = 0;
+        // Last first, so earlier hunks' offsets stay valid
+        for hunk in self
+            .hunks
+            .iter()
+            .rev()
+            .filter(|h| h.status == ProposalStatus::Accepted)
+        {
+            let range = TextRange::new(hunk.original.start, hunk.original.end);
+            if doc
+                .replace_range(&range, &self.text[hunk.revised.clone()])
+                .is_some()
+            {
+                applied += 1;
+                for ann in doc
+                    .annotations
+                    .iter_mut()
+                    .filter(|a| hunk.annotations.contains(&a.id))
+                {
+                    ann.is_resolved = true;
+                }
+            }
+        }
+        applied
+    }
+
+    fn step(&mut self, forward: bool) {
+        let last = self.hunks.len().saturating_sub(1);
+        self.selected = if forward {
+            (self.selected + 1).min(last)
+        } else {
+            self.selected.saturating_sub(1)
+        };
+    }
+
+    fn set_all(&mut self, status: ProposalStatus) {
+        for hunk in &mut self.hunks {
+            hunk.status = status;
+        }
+    }
+}
+
+/// Changed (original, revised) byte ranges between two texts, compared word
+/// by word. Changes separated only by whitespace are merged into one.
+pub fn diff_words(original: &str, revised: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    let diff = TextDiff::from_words(original, revised);
+    let offsets = |slices: &[&str]| {
+        let mut offsets = vec![0];
+        for slice in slices {
+            offsets.push(offsets.last().unwrap() + slice.len());
+        }
+        offsets
+    };
+    let old_offsets = offsets(diff.old_slices());
+    let new_offsets = offsets(diff.new_slices());
+
+    let mut hunks: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    for op in diff.ops() {
+        let (tag, old, new) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        let old = old_offsets[old.start]..old_offsets[old.end];
+        let new = new_offsets[new.start]..new_offsets[new.end];
+        match hunks.last_mut() {
+            Some((prev_old, prev_new))
+                if original[prev_old.end..old.start].trim().is_empty()
+                    && revised[prev_new.end..new.start] == original[prev_old.end..old.start] =>
+            {
+                prev_old.end = old.end;
+                prev_new.end = new.end;
+            }
+            _ => hunks.push((old, new)),
+        }
+    }
+    hunks
+}
+
+/// Whether an annotation's range overlaps a changed range. Text inserted at
+/// either end of a range doesn't touch it, but text inserted at an insertion
+/// point does.
+fn touches(range: &TextRange, changed: &Range<usize>) -> bool {
+    if changed.is_empty() {
+        if range.is_point() {
+            changed.start == range.start_offset
+        } else {
+            range.start_offset < changed.start && changed.start < range.end_offset
+        }
+    } else {
+        range.start_offset < changed.end && changed.start < range.end_offset
+    }
+}
+
+impl App {
+    /// Compare a revised version of the document with it
+    pub fn load_revision(&mut self, name: &str, text: String) {
+        let Some(doc) = &self.document else {
+            self.set_status("No document loaded");
+            return;
+        };
+        let revision = Revision::new(name, doc, text);
+        if revision.hunks.is_empty() {
+            self.set_status(&format!("{} has no changes", name));
+            return;
+        }
+        self.set_status(&format!(
+            "{} changed hunk(s) in {}",
+            revision.hunks.len(),
+            name
+        ));
+        self.revision = revision;
+        self.mode = Mode::Diff;
+    }
+
+    /// Merge the accepted hunks into the document and save it
+    fn write_revision(&mut self, platform: &mut dyn Platform) {
+        let Some(doc) = self.document.as_mut() else {
+            return;
+        };
+        let applied = self.revision.apply_accepted(doc);
+        if applied > 0 {
+            self.content_changed();
+        }
+        self.mode = Mode::Normal;
+        self.revision = Revision::default();
+        self.run_command("w", platform);
+        if let Some(status) = self.status_message.take() {
+            self.set_status(&format!("Merged {} hunk(s). {}", applied, status));
+        }
+    }
+
+    pub(crate) fn handle_diff_key(&mut self, key: Key, platform: &mut dyn Platform) {
+        if self.revision.hunks.is_empty() {
+            self.mode = Mode::Normal;
+            return;
+        }
+        let selected = self.revision.selected;
+
+        match key {
+            Key::Esc | Key::Char('q') => {
+                self.mode = Mode::Normal;
+                self.revision = Revision::default();
+                self.set_status("Revision discarded");
+            }
+            Key::Char('j') | Key::Down | Key::Char(']') => self.revision.step(true),
+            Key::Char('k') | Key::Up | Key::Char('[') => self.revision.step(false),
+            Key::Char('g') | Key::Home => self.revision.selected = 0,
+            Key::Char('G') | Key::End => self.revision.selected = self.revision.hunks.len() - 1,
+            Key::Char('a') | Key::Char('y') => {
+                self.revision.hunks[selected].status = ProposalStatus::Accepted;
+                self.revision.step(true);
+            }
+            Key::Char('x') | Key::Char('n') => {
+                self.revision.hunks[selected].status = ProposalStatus::Rejected;
+                self.revision.step(true);
+            }
+            Key::Char('u') => self.revision.hunks[selected].status = ProposalStatus::Pending,
+            Key::Char('A') => self.revision.set_all(ProposalStatus::Accepted),
+            Key::Char('X') => self.revision.set_all(ProposalStatus::Rejected),
+            Key::Char('w') => self.write_revision(platform),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Annotation;
+
+    #[test]
+    fn test_diff_hunks_and_merge() {
+        let mut doc = Document::new(
+            "Notes".to_string(),
+            "The quick brown fox jumps over the dog.".to_string(),
+        );
+        let fox = Annotation::new(
+            TextRange::new(16, 19),
+            "fox".to_string(),
+            "Which animal?".to_string(),
+        );
+        let dog = Annotation::new(
+            TextRange::new(35, 38),
+            "dog".to_string(),
+            "Lazy?".to_string(),
+        );
+        let (fox_id, dog_id) = (fox.id, dog.id);
+        doc.add_annotation(fox);
+        doc.add_annotation(dog);
+
+        let mut revision = Revision::new(
+            "revised.md",
+            &doc,
+            "The slow red fox jumps over the lazy dog.".to_string(),
+        );
+        assert_eq!(revision.hunks.len(), 2);
+        // "quick brown" -> "slow red" is one hunk, touching no annotation, and
+        // "lazy " inserted before "dog" doesn't touch it either
+        assert_eq!(
+            &doc.content[revision.hunks[0].original.clone()],
+            "quick brown"
+        );
+        assert_eq!(
+            &revision.text[revision.hunks[0].revised.clone()],
+            "slow red"
+        );
+        assert!(revision.hunks[0].annotations.is_empty());
+        assert!(revision.hunks[1].annotations.is_empty());
+        assert_eq!(
+            revision.segments(&doc.content)[1],
+            Segment::Removed("quick brown", 0)
+        );
+
+        revision.hunks[0].status = ProposalStatus::Rejected;
+        revision.hunks[1].status = ProposalStatus::Accepted;
+        assert_eq!(revision.apply_accepted(&mut doc), 1);
+        assert_eq!(doc.content, "The quick brown fox jumps over the lazy dog.");
+        let dog = doc.annotations.iter().find(|a| a.id == dog_id).unwrap();
+        assert!(!dog.is_resolved);
+        assert_eq!(dog.selected_text, "dog");
+        assert_eq!(dog.range, TextRange::new(40, 43));
+        assert!(
+            !doc.annotations
+                .iter()
+                .find(|a| a.id == fox_id)
+                .unwrap()
+                .is_resolved
+        );
+
+        // Replacing the annotated text resolves it
+        let mut revision = Revision::new(
+            "revised.md",
+            &doc,
+            "The quick brown fox jumps over the lazy hound.".to_string(),
+        );
+        assert_eq!(revision.hunks[0].annotations, vec![dog_id]);
+        revision.hunks[0].status = ProposalStatus::Accepted;
+        revision.apply_accepted(&mut doc);
+        assert!(
+            doc.annotations
+                .iter()
+                .find(|a| a.id == dog_id)
+                .unwrap()
+                .is_resolved
+        );
+    }
+}
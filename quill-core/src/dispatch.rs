@@ -0,0 +1,575 @@
+use crate::app::{App, Focus, InputTarget, Mode};
+use crate::export::to_json;
+use crate::keymap::{Action, Key, KeyChord, KeyContext};
+use crate::model::{Category, Scope, Severity};
+use crate::platform::Platform;
+
+/// File name for JSON exports
+pub const EXPORT_FILENAME: &str = "document.json";
+
+/// File name for saved Markdown prompts
+pub const PROMPT_FILENAME: &str = "document.md";
+
+/// Status shown instead of quitting or opening a file over edited text
+pub const UNSAVED_CHANGES: &str = "unsaved changes (:w to save, :q! to discard)";
+
+impl App {
+    /// Handle a key press in the current mode
+    pub fn handle_key(&mut self, chord: KeyChord, platform: &mut dyn Platform) {
+        // Clear status on any key
+        self.clear_status();
+
+        match self.mode {
+            Mode::Normal => {
+                if let Some(action) = self.keymap.lookup(KeyContext::Normal, chord) {
+                    self.dispatch(action, platform);
+                }
+            }
+            Mode::Visual => {
+                if let Some(action) = self.keymap.lookup(KeyContext::Visual, chord) {
+                    self.dispatch(action, platform);
+                }
+            }
+            Mode::Input => self.handle_input_key(chord.key, platform),
+            Mode::SeverityPicker => self.handle_severity_picker_key(chord.key),
+            Mode::CategoryPicker => self.handle_category_picker_key(chord.key),
+            Mode::Chooser => self.handle_chooser_key(chord.key),
+            Mode::Detail => self.handle_detail_key(chord.key),
+            Mode::Preview => self.handle_preview_key(chord.key, platform),
+            Mode::Review => self.handle_review_key(chord.key),
+            Mode::Output => self.handle_output_key(chord.key, platform),
+            Mode::Diff => self.handle_diff_key(chord.key, platform),
+            Mode::Help => self.mode = Mode::Normal,
+        }
+    }
+
+    /// Whether the document text was edited since it was saved, in which case
+    /// quitting or opening another file would lose it; says so in the status bar
+    pub(crate) fn refuse_unsaved(&mut self) -> bool {
+        if self.content_modified {
+            self.set_status(UNSAVED_CHANGES);
+        }
+        self.content_modified
+    }
+
+    /// Run a named action
+    pub fn dispatch(&mut self, action: Action, platform: &mut dyn Platform) {
+        let visual = self.mode == Mode::Visual;
+
+        match action {
+            Action::Quit => {
+                if !self.refuse_unsaved() {
+                    self.running = false;
+                }
+            }
+            Action::Help => self.mode = Mode::Help,
+
+            // Navigation
+            Action::MoveDown => match self.focus {
+                Focus::Sidebar if !visual => self.next_annotation(),
+                Focus::Outline if !visual => self.next_heading(),
+                _ => self.move_down(),
+            },
+            Action::MoveUp => match self.focus {
+                Focus::Sidebar if !visual => self.prev_annotation(),
+                Focus::Outline if !visual => self.prev_heading(),
+                _ => self.move_up(),
+            },
+            Action::MoveLeft => self.move_left(),
+            Action::MoveRight => self.move_right(),
+            Action::WordForward => self.move_word_forward(),
+            Action::WordBack => self.move_word_back(),
+            Action::MoveTop => self.move_to_top(),
+            Action::MoveBottom => self.move_to_bottom(),
+
+            // Annotation navigation
+            Action::NextAnnotation => self.next_annotation(),
+            Action::PrevAnnotation => self.prev_annotation(),
+
+            // Visual mode
+            Action::VisualMode => self.enter_visual_mode(),
+            Action::Cancel => {
+                self.mode = Mode::Normal;
+                self.selection_start = None;
+                self.selection_end = None;
+            }
+            Action::AddAnnotation => self.start_annotation(),
+            Action::AnnotatePoint => self.start_point_annotation(),
+            Action::CommentSection => self.start_general_comment(Scope::Section),
+            Action::CommentDocument => self.start_general_comment(Scope::Document),
+
+            // Annotation actions
+            Action::DeleteAnnotation => {
+                self.delete_selected_annotation();
+            }
+            Action::ToggleResolved => {
+                self.toggle_selected_resolved();
+            }
+            Action::ShowDetails => self.open_detail(),
+            Action::Suggest => self.start_suggestion(),
+            Action::ApplySuggestion => {
+                self.apply_suggestion();
+            }
+            Action::ClearSuggestion => {
+                self.set_suggestion(None);
+            }
+            Action::CycleInlineComments => self.cycle_inline_comments(),
+            Action::CycleLineNumbers => self.cycle_gutter(),
+            Action::CycleTheme => self.cycle_theme(),
+            Action::Select => match self.focus {
+                Focus::Editor => self.select_at_cursor(),
+                Focus::Sidebar => self.open_detail(),
+                Focus::Outline => {
+                    self.jump_to_selected_heading();
+                    self.focus = Focus::Editor;
+                }
+            },
+
+            // Filters
+            Action::ToggleHideResolved => self.toggle_hide_resolved(),
+            Action::CycleSeverityFilter => self.cycle_severity_filter(),
+            Action::CycleCategoryFilter => self.cycle_category_filter(),
+            Action::ClearFilter => self.clear_filter(),
+            Action::ToggleExportScope => self.toggle_export_scoped(),
+            Action::Search => {
+                self.input_buffer = self.filter.query.clone();
+                self.input_target = InputTarget::Search;
+                self.mode = Mode::Input;
+            }
+
+            // Sort and group
+            Action::CycleSort => self.cycle_sort_order(),
+            Action::ToggleGrouping => self.toggle_grouping(),
+            Action::CollapseGroup => self.collapse_selected_group(),
+            Action::ExpandGroups => self.expand_all_groups(),
+
+            // Focus and outline
+            Action::ToggleFocus => self.toggle_focus(),
+            Action::ToggleOutline => self.toggle_outline(),
+
+            Action::CommandLine => self.start_command(),
+
+            // Export
+            Action::Export => {
+                if let Some(doc) = self.export_document() {
+                    match to_json(&doc) {
+                        Ok(json) => match platform.save_export(EXPORT_FILENAME, &json) {
+                            Ok(location) => self.set_status(&format!("Exported to {}", location)),
+                            Err(e) => self.set_status(&format!("Export failed: {}", e)),
+                        },
+                        Err(e) => self.set_status(&format!("Serialization failed: {}", e)),
+                    }
+                }
+            }
+            Action::ExportPrompt => {
+                if let Some(prompt) = self.prompt_text() {
+                    self.copy_text("Prompt", &prompt, platform);
+                }
+            }
+            Action::PreviewPrompt => self.open_preview(),
+            Action::Send => self.start_send(None, platform),
+            Action::ShowOutput => {
+                if self.output.command.is_empty() {
+                    self.set_status("Nothing has been sent yet");
+                } else {
+                    self.mode = Mode::Output;
+                }
+            }
+            Action::CopyJson => {
+                if let Some(doc) = self.export_document() {
+                    match to_json(&doc) {
+                        Ok(json) => self.copy_text("JSON", &json, platform),
+                        Err(e) => self.set_status(&format!("Serialization failed: {}", e)),
+                    }
+                }
+            }
+            Action::CopyAnnotation => match self.annotation_prompt() {
+                Some(text) => self.copy_text("Annotation", &text, platform),
+                None => self.set_status("No annotation selected"),
+            },
+
+            // Open file
+            Action::OpenFile => {
+                if !self.refuse_unsaved() && !platform.pick_file() {
+                    self.input_buffer.clear();
+                    self.input_target = InputTarget::FilePath;
+                    self.mode = Mode::Input;
+                }
+            }
+            Action::LoadResponse => {
+                if self.document.is_none() {
+                    self.set_status("No document loaded");
+                } else if !platform.pick_response() {
+                    self.input_buffer.clear();
+                    self.input_target = InputTarget::ResponsePath;
+                    self.mode = Mode::Input;
+                }
+            }
+            Action::LoadRevision => {
+                if self.document.is_none() {
+                    self.set_status("No document loaded");
+                } else if !platform.pick_revision() {
+                    self.input_buffer.clear();
+                    self.input_target = InputTarget::RevisionPath;
+                    self.mode = Mode::Input;
+                }
+            }
+        }
+
+        if visual && self.mode == Mode::Visual {
+            self.update_selection();
+        }
+    }
+
+    /// Copy `text` to the clipboard and report the outcome in the status bar
+    pub(crate) fn copy_text(&mut self, what: &str, text: &str, platform: &mut dyn Platform) {
+        match platform.copy_to_clipboard(text) {
+            Ok(how) => self.set_status(&format!(
+                "{} {} ({} chars)",
+                what,
+                how,
+                text.chars().count()
+            )),
+            Err(e) => self.set_status(&format!(
+                "{} generated ({} chars), but copying failed: {}",
+                what,
+                text.chars().count(),
+                e
+            )),
+        }
+    }
+
+    fn handle_input_key(&mut self, key: Key, platform: &mut dyn Platform) {
+        if key != Key::Tab {
+            self.completions.clear();
+        }
+
+        match key {
+            Key::Esc => {
+                self.mode = Mode::Normal;
+                self.input_buffer.clear();
+                self.pending_range = None;
+            }
+            Key::Enter => match self.input_target {
+                InputTarget::Comment => {
+                    self.complete_annotation();
+                }
+                InputTarget::Suggestion => {
+                    let suggestion = std::mem::take(&mut self.input_buffer);
+                    self.mode = Mode::Normal;
+                    self.set_suggestion(Some(suggestion));
+                }
+                InputTarget::FilePath => {
+                    let path = std::mem::take(&mut self.input_buffer);
+                    self.mode = Mode::Normal;
+                    match platform.open_file(&path) {
+                        Ok(doc) => self.open_document(doc, &path),
+                        Err(e) => self.set_status(&format!("Error: {}", e)),
+                    }
+                }
+                InputTarget::ResponsePath => {
+                    let path = std::mem::take(&mut self.input_buffer);
+                    self.mode = Mode::Normal;
+                    match platform.read_text(&path) {
+                        Ok(text) => self.load_response(&text),
+                        Err(e) => self.set_status(&format!("Error: {}", e)),
+                    }
+                }
+                InputTarget::RevisionPath => {
+                    let path = std::mem::take(&mut self.input_buffer);
+                    self.mode = Mode::Normal;
+                    match platform.read_text(&path) {
+                        Ok(text) => self.load_revision(&path, text),
+                        Err(e) => self.set_status(&format!("Error: {}", e)),
+                    }
+                }
+                InputTarget::Search => {
+                    let query = std::mem::take(&mut self.input_buffer);
+                    self.mode = Mode::Normal;
+                    self.set_filter_query(&query);
+                }
+                InputTarget::Command => {
+                    let line = std::mem::take(&mut self.input_buffer);
+                    self.mode = Mode::Normal;
+                    self.run_command(&line, platform);
+                }
+            },
+            Key::Tab if self.input_target == InputTarget::Command => self.complete_command(),
+            Key::Backspace => {
+                self.input_buffer.pop();
+            }
+            Key::Char(c) => self.input_buffer.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_severity_picker_key(&mut self, key: Key) {
+        let len = Severity::all().len();
+        match key {
+            Key::Esc => {
+                self.mode = Mode::Normal;
+                self.pending_range = None;
+            }
+            Key::Char('j') | Key::Down => {
+                self.severity_selected = (self.severity_selected + 1) % len;
+            }
+            Key::Char('k') | Key::Up => {
+                self.severity_selected = (self.severity_selected + len - 1) % len;
+            }
+            Key::Enter => {
+                self.pending_severity = Severity::all()[self.severity_selected];
+                self.mode = Mode::CategoryPicker;
+            }
+            // Quick select
+            Key::Char(c @ '1'..='3') => {
+                self.pending_severity = Severity::all()[c as usize - '1' as usize];
+                self.mode = Mode::CategoryPicker;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_category_picker_key(&mut self, key: Key) {
+        let total = Category::all().len() + 1; // +1 for "None"
+        match key {
+            Key::Esc => {
+                self.mode = Mode::Normal;
+                self.pending_range = None;
+            }
+            Key::Char('j') | Key::Down => {
+                self.category_selected = (self.category_selected + 1) % total;
+            }
+            Key::Char('k') | Key::Up => {
+                self.category_selected = (self.category_selected + total - 1) % total;
+            }
+            Key::Enter => {
+                self.pending_category = if self.category_selected == 0 {
+                    None
+                } else {
+                    Some(Category::all()[self.category_selected - 1])
+                };
+                self.start_comment_input();
+            }
+            // Quick select
+            Key::Char('0') => {
+                self.pending_category = None;
+                self.start_comment_input();
+            }
+            _ => {}
+        }
+    }
+
+    fn start_comment_input(&mut self) {
+        self.input_buffer.clear();
+        self.input_target = InputTarget::Comment;
+        self.mode = Mode::Input;
+    }
+
+    fn handle_chooser_key(&mut self, key: Key) {
+        let count = self.annotations_at_cursor().len();
+        if count == 0 {
+            self.mode = Mode::Normal;
+            return;
+        }
+
+        match key {
+            Key::Esc => self.mode = Mode::Normal,
+            Key::Char('j') | Key::Down => {
+                self.chooser_selected = (self.chooser_selected + 1) % count;
+            }
+            Key::Char('k') | Key::Up => {
+                self.chooser_selected = (self.chooser_selected + count - 1) % count;
+            }
+            Key::Enter => self.choose_at_cursor(self.chooser_selected),
+            // Quick select
+            Key::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if index < count {
+                    self.choose_at_cursor(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_detail_key(&mut self, key: Key) {
+        match key {
+            Key::Esc | Key::Enter | Key::Char('q') | Key::Char('i') => self.close_detail(),
+            Key::Char('j') | Key::Down => {
+                self.detail_scroll = self.detail_scroll.saturating_add(1);
+            }
+            Key::Char('k') | Key::Up => {
+                self.detail_scroll = self.detail_scroll.saturating_sub(1);
+            }
+            Key::Char(']') => {
+                self.next_annotation();
+                self.detail_scroll = 0;
+            }
+            Key::Char('[') => {
+                self.prev_annotation();
+                self.detail_scroll = 0;
+            }
+            Key::Char('c') => self.start_suggestion(),
+            Key::Char('A') => {
+                self.apply_suggestion();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::generate_prompt;
+    use crate::model::Document;
+    use anyhow::{anyhow, Result};
+
+    /// Platform that records exports and serves a single file
+    #[derive(Default)]
+    struct MockPlatform {
+        exports: Vec<(String, String)>,
+        clipboard: Option<String>,
+    }
+
+    impl Platform for MockPlatform {
+        fn open_file(&mut self, path: &str) -> Result<Document> {
+            match path {
+                "draft.md" => Ok(Document::new(
+                    "draft".to_string(),
+                    "Hello world".to_string(),
+                )),
+                _ => Err(anyhow!("not found")),
+            }
+        }
+
+        fn save_export(&mut self, filename: &str, contents: &str) -> Result<String> {
+            self.exports
+                .push((filename.to_string(), contents.to_string()));
+            Ok(filename.to_string())
+        }
+
+        fn save_sidecar(&mut self, doc: &Document) -> Result<String> {
+            self.save_export("sidecar", &crate::sidecar::to_sidecar_json(doc)?)
+        }
+
+        fn copy_to_clipboard(&mut self, text: &str) -> Result<String> {
+            self.clipboard = Some(text.to_string());
+            Ok("copied to clipboard".to_string())
+        }
+    }
+
+    fn type_keys(app: &mut App, platform: &mut MockPlatform, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(KeyChord::new(Key::Char(c)), platform);
+        }
+    }
+
+    #[test]
+    fn test_open_annotate_and_export() {
+        let mut app = App::new();
+        let mut platform = MockPlatform::default();
+
+        type_keys(&mut app, &mut platform, "odraft.md");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(
+            app.document.as_ref().map(|d| d.title.as_str()),
+            Some("draft")
+        );
+        assert_eq!(app.status_message.as_deref(), Some("Loaded draft.md"));
+
+        // Select "Hello", must-fix, no category, comment
+        type_keys(&mut app, &mut platform, "vllll");
+        type_keys(&mut app, &mut platform, "a10Tighten");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        let doc = app.document.as_ref().unwrap();
+        assert_eq!(doc.annotations.len(), 1);
+        assert_eq!(doc.annotations[0].severity, Severity::MustFix);
+        assert_eq!(doc.annotations[0].comment, "Tighten");
+
+        type_keys(&mut app, &mut platform, "e");
+        assert_eq!(platform.exports.len(), 1);
+        assert_eq!(platform.exports[0].0, EXPORT_FILENAME);
+        assert!(platform.exports[0].1.contains("Tighten"));
+
+        type_keys(&mut app, &mut platform, "E");
+        let prompt = generate_prompt(app.document.as_ref().unwrap());
+        assert_eq!(platform.clipboard.as_deref(), Some(prompt.as_str()));
+
+        // A single annotation is copied with the selected template
+        app.prompt_options.structured = true;
+        app.dispatch(Action::CopyAnnotation, &mut platform);
+        let copied = platform.clipboard.as_deref().unwrap();
+        let id = app.document.as_ref().unwrap().annotations[0].id;
+        assert!(copied.contains(&format!("**\"Hell\"**\n- ID: {}\n- Feedback: Tighten", id)));
+    }
+
+    #[test]
+    fn test_command_line() {
+        let mut app = App::new();
+        let mut platform = MockPlatform::default();
+        type_keys(&mut app, &mut platform, ":e draft.md");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert!(app.document.is_some());
+
+        type_keys(&mut app, &mut platform, ":set theme=la");
+        app.handle_key(KeyChord::new(Key::Tab), &mut platform);
+        assert_eq!(app.input_buffer, "set theme=latte");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(app.theme.name, "latte");
+
+        type_keys(&mut app, &mut platform, ":w");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(
+            platform.exports.last().map(|e| e.0.as_str()),
+            Some("sidecar")
+        );
+
+        type_keys(&mut app, &mut platform, ":bogus");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Error: Unknown command: bogus")
+        );
+    }
+
+    #[test]
+    fn test_unsaved_changes_block_quit_and_open() {
+        let mut app = App::new();
+        let mut platform = MockPlatform::default();
+        app.load_document(Document::new("Test".to_string(), "Hello".to_string()));
+        app.content_modified = true;
+
+        type_keys(&mut app, &mut platform, "q");
+        assert!(app.running);
+        assert_eq!(app.status_message.as_deref(), Some(UNSAVED_CHANGES));
+
+        type_keys(&mut app, &mut platform, "o");
+        assert_eq!(app.mode, Mode::Normal);
+        type_keys(&mut app, &mut platform, ":e draft.md");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(app.document.as_ref().unwrap().title, "Test");
+
+        type_keys(&mut app, &mut platform, ":q");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert!(app.running);
+
+        type_keys(&mut app, &mut platform, ":e! draft.md");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(app.document.as_ref().unwrap().title, "draft");
+        app.content_modified = true;
+        type_keys(&mut app, &mut platform, ":q!");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn test_open_missing_file_reports_error() {
+        let mut app = App::new();
+        let mut platform = MockPlatform::default();
+        type_keys(&mut app, &mut platform, "omissing");
+        app.handle_key(KeyChord::new(Key::Enter), &mut platform);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.status_message.as_deref(), Some("Error: not found"));
+    }
+}
@@ -0,0 +1,202 @@
+//! Excerpts of a document around annotations, for prompts that leave out the full text
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// How much surrounding text an excerpt includes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExcerptContext {
+    /// Sentences on each side of the annotated text
+    Sentences(usize),
+    /// Paragraphs on each side of the annotated text
+    Paragraphs(usize),
+}
+
+impl Default for ExcerptContext {
+    fn default() -> Self {
+        ExcerptContext::Paragraphs(0)
+    }
+}
+
+impl fmt::Display for ExcerptContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExcerptContext::Sentences(n) => write!(f, "{}s", n),
+            ExcerptContext::Paragraphs(n) => write!(f, "{}p", n),
+        }
+    }
+}
+
+impl FromStr for ExcerptContext {
+    type Err = String;
+
+    /// Parse a count and unit, such as `2s`, `1p` or `2 sentences`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let count: usize = s[..digits]
+            .parse()
+            .map_err(|_| format!("Expected a count such as 2s or 1p, got {}", s))?;
+        match s[digits..].trim() {
+            "s" | "sentence" | "sentences" => Ok(ExcerptContext::Sentences(count)),
+            "p" | "paragraph" | "paragraphs" => Ok(ExcerptContext::Paragraphs(count)),
+            unit => Err(format!("Unknown excerpt unit: {} (use s or p)", unit)),
+        }
+    }
+}
+
+/// Whole-line ranges covering each annotated range plus its context,
+/// sorted and merged where they touch
+pub fn excerpt_ranges(
+    content: &str,
+    annotated: impl IntoIterator<Item = Range<usize>>,
+    context: ExcerptContext,
+) -> Vec<Range<usize>> {
+    let (units, n) = match context {
+        ExcerptContext::Sentences(n) => (sentences(content), n),
+        ExcerptContext::Paragraphs(n) => (paragraphs(content), n),
+    };
+    if units.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranges: Vec<Range<usize>> = annotated
+        .into_iter()
+        .map(|range| {
+            let first = units
+                .iter()
+                .position(|u| u.end > range.start)
+                .unwrap_or(units.len() - 1);
+            let last = units
+                .iter()
+                .rposition(|u| u.start < range.end.max(range.start + 1))
+                .unwrap_or(first)
+                .max(first);
+            let start = units[first.saturating_sub(n)].start;
+            let end = units[(last + n).min(units.len() - 1)].end;
+            line_start(content, start)..line_end(content, end)
+        })
+        .collect();
+
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(prev) if range.start <= prev.end + 1 => prev.end = prev.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Prefix each line of `text` with its line number, starting at `first_line`
+pub fn number_lines(text: &str, first_line: usize) -> String {
+    let width = (first_line + text.lines().count().saturating_sub(1))
+        .to_string()
+        .len();
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$} | {}", first_line + i, line, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn line_start(content: &str, offset: usize) -> usize {
+    content[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn line_end(content: &str, offset: usize) -> usize {
+    if offset > 0 && content.as_bytes().get(offset - 1) == Some(&b'\n') {
+        return offset - 1;
+    }
+    content[offset..]
+        .find('\n')
+        .map_or(content.len(), |i| offset + i)
+}
+
+/// Runs of non-blank lines
+fn paragraphs(content: &str) -> Vec<Range<usize>> {
+    let mut units = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in content.split('\n') {
+        let end = offset + line.len();
+        if line.trim().is_empty() {
+            if let Some(s) = start.take() {
+                units.push(s..offset.saturating_sub(1));
+            }
+        } else if start.is_none() {
+            start = Some(offset);
+        }
+        offset = end + 1;
+    }
+    if let Some(s) = start {
+        units.push(s..content.len());
+    }
+    units
+}
+
+/// Text up to sentence-ending punctuation or a paragraph break
+fn sentences(content: &str) -> Vec<Range<usize>> {
+    let mut units = Vec::new();
+    for paragraph in paragraphs(content) {
+        let text = &content[paragraph.clone()];
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let at_break = matches!(c, '.' | '!' | '?')
+                && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+            if at_break {
+                let end = i + c.len_utf8();
+                units.push(paragraph.start + start..paragraph.start + end);
+                // Skip the whitespace before the next sentence
+                while chars.peek().is_some_and(|(_, next)| next.is_whitespace()) {
+                    chars.next();
+                }
+                start = chars.peek().map_or(text.len(), |(j, _)| *j);
+            }
+        }
+        if start < text.len() {
+            units.push(paragraph.start + start..paragraph.end);
+        }
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excerpt_ranges() {
+        let content = "Intro line.\n\nFirst one. Second one.\nThird one.\n\nLast para.";
+        let second = content.find("Second").unwrap();
+        let annotated = second..second + 6;
+
+        let ranges = excerpt_ranges(
+            content,
+            Some(annotated.clone()),
+            ExcerptContext::Paragraphs(0),
+        );
+        assert_eq!(
+            &content[ranges[0].clone()],
+            "First one. Second one.\nThird one."
+        );
+
+        let ranges = excerpt_ranges(content, Some(annotated), ExcerptContext::Paragraphs(1));
+        assert_eq!(ranges, vec![0..content.len()]);
+
+        // Sentence context still widens to whole lines
+        let third = content.find("Third").unwrap();
+        let annotated = third..third + 5;
+        let ranges = excerpt_ranges(content, Some(annotated), ExcerptContext::Sentences(0));
+        assert_eq!(&content[ranges[0].clone()], "Third one.");
+
+        assert_eq!("2s".parse(), Ok(ExcerptContext::Sentences(2)));
+        assert_eq!("1 paragraph".parse(), Ok(ExcerptContext::Paragraphs(1)));
+        assert!("3x".parse::<ExcerptContext>().is_err());
+
+        assert_eq!(number_lines("a\nb", 9), " 9 | a\n10 | b");
+    }
+}
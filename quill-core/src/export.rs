@@ -1,6 +1,9 @@
 use serde::Serialize;
 
-use crate::model::{Annotation, Document, Severity};
+use crate::excerpt::ExcerptContext;
+use crate::model::{Annotation, Document, Scope, Severity};
+use crate::outline::parse_headings;
+use crate::template::Template;
 
 /// Export format matching macOS Quill app
 #[derive(Debug, Serialize)]
@@ -23,6 +26,16 @@ pub struct ExportAnnotation {
     pub category: Option<String>,
     pub severity: String,
     pub comment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    /// Where to insert text, for insertion points: `Insert at line 3, after ...`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_at: Option<String>,
+    #[serde(skip_serializing_if = "Scope::is_range")]
+    pub scope: Scope,
+    /// Heading of the section a section comment is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
     pub start_offset: usize,
     pub end_offset: usize,
 }
@@ -40,12 +53,29 @@ impl From<&Annotation> for ExportAnnotation {
             }
             .to_string(),
             comment: ann.comment.clone(),
+            suggestion: ann.suggestion.clone(),
+            insert_at: None,
+            scope: ann.scope,
+            section: None,
             start_offset: ann.range.start_offset,
             end_offset: ann.range.end_offset,
         }
     }
 }
 
+impl ExportAnnotation {
+    /// Export an annotation with the wording that depends on its document:
+    /// where an insertion point is and which section a comment is on
+    pub fn new(doc: &Document, ann: &Annotation) -> Self {
+        Self {
+            insert_at: (!ann.is_general() && ann.range.is_point())
+                .then(|| insertion_point(doc, ann)),
+            section: (ann.scope == Scope::Section).then(|| section_title(doc, ann)),
+            ..Self::from(ann)
+        }
+    }
+}
+
 impl From<&Document> for ExportDocument {
     fn from(doc: &Document) -> Self {
         let prompt = generate_prompt(doc);
@@ -55,63 +85,135 @@ impl From<&Document> for ExportDocument {
             title: doc.title.clone(),
             content: doc.content.clone(),
             word_count: doc.word_count(),
-            annotations: doc.annotations.iter().map(ExportAnnotation::from).collect(),
+            annotations: doc
+                .annotations
+                .iter()
+                .map(|a| ExportAnnotation::new(doc, a))
+                .collect(),
             prompt,
         }
     }
 }
 
-/// Generate a Claude-ready prompt from a document
-pub fn generate_prompt(doc: &Document) -> String {
-    let mut prompt = String::new();
+/// Choices that change what goes into a generated prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptOptions {
+    /// Include the whole document ahead of the annotations
+    pub full_text: bool,
+    /// Include numbered excerpts around each annotation instead of the full text
+    pub excerpts: Option<ExcerptContext>,
+    /// Split into one prompt per group of sections above this many tokens
+    pub token_budget: Option<usize>,
+    /// Ask for revisions as JSON keyed by annotation ID, for loading back with
+    /// [`crate::response::parse_response`]
+    pub structured: bool,
+}
 
-    prompt.push_str(&format!("## Document: {}\n\n", doc.title));
-    prompt.push_str("Please review and edit this document based on the following annotations.\n\n");
+impl Default for PromptOptions {
+    fn default() -> Self {
+        Self {
+            full_text: true,
+            excerpts: None,
+            token_budget: None,
+            structured: false,
+        }
+    }
+}
 
-    prompt.push_str("### Full Text\n\n");
-    prompt.push_str(&doc.content);
-    prompt.push_str("\n\n---\n\n");
+/// Line placed between prompts that were split to fit the token budget
+pub fn prompt_separator(part: usize, parts: usize) -> String {
+    format!("<!-- prompt {} of {} -->", part, parts)
+}
 
-    let unresolved: Vec<_> = doc
-        .annotations
+/// Join split prompts into one text, marking where each begins
+pub fn join_prompts(prompts: &[String]) -> String {
+    if prompts.len() == 1 {
+        return prompts[0].clone();
+    }
+    prompts
         .iter()
-        .filter(|a| !a.is_resolved)
-        .collect();
+        .enumerate()
+        .map(|(i, prompt)| format!("{}\n\n{}", prompt_separator(i + 1, prompts.len()), prompt))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
 
-    if unresolved.is_empty() {
-        prompt.push_str("No annotations to address.\n");
-        return prompt;
-    }
+/// Generate a Claude-ready prompt from a document
+pub fn generate_prompt(doc: &Document) -> String {
+    generate_prompt_with(doc, &PromptOptions::default())
+}
 
-    prompt.push_str(&format!("### Annotations ({} items)\n\n", unresolved.len()));
+/// Generate a prompt with non-default options, using the built-in template
+pub fn generate_prompt_with(doc: &Document, options: &PromptOptions) -> String {
+    Template::default().render(doc, options)
+}
 
-    // Group by severity
-    for severity in Severity::all() {
-        let items: Vec<_> = unresolved
-            .iter()
-            .filter(|a| a.severity == *severity)
-            .collect();
+/// Words of context quoted on each side of an insertion point
+pub const INSERTION_CONTEXT_WORDS: usize = 4;
 
-        if items.is_empty() {
-            continue;
-        }
+/// Describe where an insertion point is, e.g. `Insert at line 3, after "the
+/// brown fox" and before "jumps over"`
+pub fn insertion_point(doc: &Document, ann: &Annotation) -> String {
+    let (before, after) = doc.words_around(ann.range.start_offset, INSERTION_CONTEXT_WORDS);
+    let mut text = format!("Insert at line {}", doc.line_number(ann.range.start_offset));
+    if !before.is_empty() {
+        text.push_str(&format!(", after \"{}\"", before));
+    }
+    if !after.is_empty() {
+        let joiner = if before.is_empty() { "," } else { " and" };
+        text.push_str(&format!("{} before \"{}\"", joiner, after));
+    }
+    text
+}
 
-        prompt.push_str(&format!("#### {} ({})\n\n", severity.as_str(), items.len()));
+/// Title of the heading a section comment is anchored to
+pub fn section_title(doc: &Document, ann: &Annotation) -> String {
+    parse_headings(&doc.content)
+        .into_iter()
+        .find(|h| h.start_offset == ann.range.start_offset)
+        .map(|h| h.title)
+        .unwrap_or_else(|| ann.selected_text.trim_start_matches('#').trim().to_string())
+}
 
-        for ann in items {
-            prompt.push_str(&format!("**\"{}\"**\n", ann.selected_text));
-            if let Some(cat) = ann.category {
-                prompt.push_str(&format!("- Category: {}\n", cat.as_str()));
-            }
-            prompt.push_str(&format!("- Feedback: {}\n\n", ann.comment));
-        }
+/// What a general comment is on: `Whole document` or `Section "Title"`
+pub fn general_label(doc: &Document, ann: &Annotation) -> String {
+    match ann.scope {
+        Scope::Section => format!("Section \"{}\"", section_title(doc, ann)),
+        _ => "Whole document".to_string(),
     }
+}
 
-    prompt.push_str("---\n\n");
-    prompt.push_str("Please provide the revised document with all annotations addressed. ");
-    prompt.push_str("For each change, briefly note what was modified and why.");
+/// Rough token count for LLM context budgeting (about four characters per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
 
-    prompt
+/// Which prompts are still over the token budget, say when a single
+/// paragraph is longer than the budget allows
+pub fn budget_warning(prompts: &[String], budget: Option<usize>) -> Option<String> {
+    let budget = budget?;
+    let over: Vec<String> = prompts
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| estimate_tokens(p) > budget)
+        .map(|(i, _)| (i + 1).to_string())
+        .collect();
+    match over.len() {
+        0 => None,
+        _ if prompts.len() == 1 => Some(format!("the prompt is over the {}-token budget", budget)),
+        1 => Some(format!(
+            "part {} of {} is over the {}-token budget",
+            over[0],
+            prompts.len(),
+            budget
+        )),
+        _ => Some(format!(
+            "parts {} of {} are over the {}-token budget",
+            over.join(", "),
+            prompts.len(),
+            budget
+        )),
+    }
 }
 
 /// Serialize a document to JSON string
@@ -144,6 +246,44 @@ mod tests {
         assert!(json.contains("\"comment\":\"fix this\""));
     }
 
+    #[test]
+    fn test_prompt_format() {
+        let mut doc = Document::new("Notes".to_string(), "Hello world".to_string());
+        let mut ann = Annotation::new(
+            TextRange::new(0, 5),
+            "Hello".to_string(),
+            "Warmer".to_string(),
+        );
+        ann.category = Some(Category::Voice);
+        ann.severity = Severity::MustFix;
+        doc.add_annotation(ann);
+        doc.add_annotation(Annotation::new(
+            TextRange::new(6, 11),
+            "world".to_string(),
+            "Vague".to_string(),
+        ));
+
+        let expected = "## Document: Notes\n\n\
+            Please review and edit this document based on the following annotations.\n\n\
+            ### Full Text\n\nHello world\n\n---\n\n\
+            ### Annotations (2 items)\n\n\
+            #### Must Fix (1)\n\n**\"Hello\"**\n- Category: Voice\n- Feedback: Warmer\n\n\
+            #### Should Fix (1)\n\n**\"world\"**\n- Feedback: Vague\n\n\
+            ---\n\n\
+            Please provide the revised document with all annotations addressed. \
+            For each change, briefly note what was modified and why.";
+        assert_eq!(generate_prompt(&doc), expected);
+
+        doc.annotations[0].suggestion = Some("Hi".to_string());
+        doc.annotations[1].suggestion = Some(String::new());
+        let prompt = generate_prompt(&doc);
+        assert!(prompt.contains("- Feedback: Warmer\n- **Suggested edit: \"Hi\"**\n\n"));
+        assert!(prompt.contains("- Feedback: Vague\n- **Suggested edit: delete this text**\n\n"));
+
+        let empty = Document::new("Empty".to_string(), "Text".to_string());
+        assert!(generate_prompt(&empty).ends_with("---\n\nNo annotations to address.\n"));
+    }
+
     #[test]
     fn test_export_document_format() {
         let mut doc = Document::new("Test".to_string(), "Hello world".to_string());
@@ -0,0 +1,139 @@
+use crate::model::{Annotation, Category, Document, Severity};
+
+/// Criteria for which annotations are listed in the sidebar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnotationFilter {
+    pub hide_resolved: bool,
+    pub severity: Option<Severity>,
+    pub category: Option<Category>,
+    /// Case-insensitive text to search for in comments
+    pub query: String,
+}
+
+impl AnnotationFilter {
+    /// Check if an annotation passes every active criterion
+    pub fn matches(&self, ann: &Annotation) -> bool {
+        if self.hide_resolved && ann.is_resolved {
+            return false;
+        }
+        if self.severity.is_some_and(|s| s != ann.severity) {
+            return false;
+        }
+        if self.category.is_some() && self.category != ann.category {
+            return false;
+        }
+        if !self.query.is_empty()
+            && !ann
+                .comment
+                .to_lowercase()
+                .contains(&self.query.to_lowercase())
+        {
+            return false;
+        }
+        true
+    }
+
+    /// Whether any criterion is set
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Cycle severity filter: none -> each severity -> none
+    pub fn cycle_severity(&mut self) {
+        self.severity = next_in(Severity::all(), self.severity);
+    }
+
+    /// Cycle category filter: none -> each category -> none
+    pub fn cycle_category(&mut self) {
+        self.category = next_in(Category::all(), self.category);
+    }
+
+    /// Short human-readable summary of the active criteria
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.hide_resolved {
+            parts.push("unresolved".to_string());
+        }
+        if let Some(sev) = self.severity {
+            parts.push(sev.short().to_string());
+        }
+        if let Some(cat) = self.category {
+            parts.push(cat.as_str().to_string());
+        }
+        if !self.query.is_empty() {
+            parts.push(format!("\"{}\"", self.query));
+        }
+        parts.join(", ")
+    }
+
+    /// Copy of the document keeping only matching annotations (for scoped exports)
+    pub fn apply(&self, doc: &Document) -> Document {
+        let mut scoped = doc.clone();
+        scoped.annotations.retain(|a| self.matches(a));
+        scoped
+    }
+}
+
+fn next_in<T: Copy + PartialEq>(all: &[T], current: Option<T>) -> Option<T> {
+    match current {
+        None => all.first().copied(),
+        Some(value) => all
+            .iter()
+            .position(|v| *v == value)
+            .and_then(|i| all.get(i + 1))
+            .copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TextRange;
+
+    fn annotation(severity: Severity, comment: &str, resolved: bool) -> Annotation {
+        let mut ann = Annotation::new(TextRange::new(0, 1), "x".to_string(), comment.to_string());
+        ann.severity = severity;
+        ann.is_resolved = resolved;
+        ann
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let open_must = annotation(Severity::MustFix, "Too passive", false);
+        let resolved = annotation(Severity::Consider, "typo", true);
+
+        let mut filter = AnnotationFilter::default();
+        assert!(!filter.is_active());
+        assert!(filter.matches(&open_must) && filter.matches(&resolved));
+
+        filter.hide_resolved = true;
+        assert!(filter.matches(&open_must) && !filter.matches(&resolved));
+
+        filter.hide_resolved = false;
+        filter.severity = Some(Severity::MustFix);
+        filter.query = "PASSIVE".to_string();
+        assert!(filter.matches(&open_must) && !filter.matches(&resolved));
+
+        filter.category = Some(Category::Voice);
+        assert!(!filter.matches(&open_must));
+    }
+
+    #[test]
+    fn test_cycle_severity() {
+        let mut filter = AnnotationFilter::default();
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            filter.cycle_severity();
+            seen.push(filter.severity);
+        }
+        assert_eq!(
+            seen,
+            vec![
+                Some(Severity::MustFix),
+                Some(Severity::ShouldFix),
+                Some(Severity::Consider),
+                None
+            ]
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Platform-neutral key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Esc,
+    Tab,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+/// A key together with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: Key,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl KeyChord {
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            alt: false,
+        }
+    }
+
+    pub fn ctrl(key: Key) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::new(key)
+        }
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    /// Parse chords such as `j`, `G`, `Enter`, `ctrl+s` or `alt+Down`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chord = KeyChord::new(Key::Esc);
+        let mut rest = s;
+        loop {
+            let lower = rest.to_lowercase();
+            if lower.starts_with("ctrl+") || lower.starts_with("ctrl-") {
+                chord.ctrl = true;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt+") || lower.starts_with("alt-") {
+                chord.alt = true;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        chord.key = match (chars.next(), chars.next()) {
+            (Some(c), None) => Key::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => Key::Enter,
+                "esc" | "escape" => Key::Esc,
+                "tab" => Key::Tab,
+                "backspace" => Key::Backspace,
+                "delete" | "del" => Key::Delete,
+                "up" => Key::Up,
+                "down" => Key::Down,
+                "left" => Key::Left,
+                "right" => Key::Right,
+                "home" => Key::Home,
+                "end" => Key::End,
+                "pageup" => Key::PageUp,
+                "pagedown" => Key::PageDown,
+                "space" => Key::Char(' '),
+                other => match other.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => Key::F(n),
+                    None => return Err(format!("Unknown key: {}", s)),
+                },
+            },
+        };
+        Ok(chord)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        match self.key {
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::F(n) => write!(f, "F{}", n),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// Mode a binding applies in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    Normal,
+    Visual,
+}
+
+macro_rules! actions {
+    ($($variant:ident => $name:literal, $section:literal, $desc:literal;)*) => {
+        /// Named action that a key chord can be bound to
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Action {
+            $($variant,)*
+        }
+
+        impl Action {
+            pub fn all() -> &'static [Action] {
+                &[$(Action::$variant,)*]
+            }
+
+            /// Name used in config files
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Action::$variant => $name,)*
+                }
+            }
+
+            /// Help overlay section
+            pub fn section(&self) -> &'static str {
+                match self {
+                    $(Action::$variant => $section,)*
+                }
+            }
+
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(Action::$variant => $desc,)*
+                }
+            }
+        }
+    };
+}
+
+actions! {
+    MoveDown => "move-down", "Navigation", "Move down / next item";
+    MoveUp => "move-up", "Navigation", "Move up / previous item";
+    MoveLeft => "move-left", "Navigation", "Move left";
+    MoveRight => "move-right", "Navigation", "Move right";
+    WordForward => "word-forward", "Navigation", "Next word";
+    WordBack => "word-back", "Navigation", "Previous word";
+    MoveTop => "move-top", "Navigation", "Go to top";
+    MoveBottom => "move-bottom", "Navigation", "Go to bottom";
+    NextAnnotation => "next-annotation", "Navigation", "Next annotation";
+    PrevAnnotation => "prev-annotation", "Navigation", "Previous annotation";
+    ToggleFocus => "toggle-focus", "Navigation", "Cycle editor/sidebar/outline";
+    ToggleOutline => "toggle-outline", "Navigation", "Toggle outline pane";
+    Select => "select", "Navigation", "Open item / annotation at cursor";
+    VisualMode => "visual-mode", "Annotations", "Enter visual mode";
+    Cancel => "cancel", "Annotations", "Cancel selection";
+    AddAnnotation => "add-annotation", "Annotations", "Add annotation to selection";
+    AnnotatePoint => "annotate-point", "Annotations", "Mark an insertion point at the cursor";
+    CommentSection => "comment-section", "Annotations", "Comment on the section at the cursor";
+    CommentDocument => "comment-document", "Annotations", "Comment on the whole document";
+    DeleteAnnotation => "delete-annotation", "Annotations", "Delete annotation";
+    ToggleResolved => "toggle-resolved", "Annotations", "Toggle resolved";
+    ShowDetails => "show-details", "Annotations", "Show annotation details";
+    Suggest => "suggest", "Annotations", "Suggest replacement text";
+    ApplySuggestion => "apply-suggestion", "Annotations", "Apply suggestion to the text";
+    ClearSuggestion => "clear-suggestion", "Annotations", "Clear suggestion";
+    ToggleHideResolved => "toggle-hide-resolved", "Sidebar", "Hide/show resolved";
+    CycleSeverityFilter => "cycle-severity-filter", "Sidebar", "Cycle severity filter";
+    CycleCategoryFilter => "cycle-category-filter", "Sidebar", "Cycle category filter";
+    Search => "search", "Sidebar", "Search comments";
+    ClearFilter => "clear-filter", "Sidebar", "Clear filters";
+    ToggleExportScope => "toggle-export-scope", "Sidebar", "Scope exports to filter";
+    CycleSort => "cycle-sort", "Sidebar", "Cycle sort order";
+    ToggleGrouping => "toggle-grouping", "Sidebar", "Toggle grouping";
+    CollapseGroup => "collapse-group", "Sidebar", "Collapse group";
+    ExpandGroups => "expand-groups", "Sidebar", "Expand all groups";
+    CycleInlineComments => "cycle-inline-comments", "View", "Cycle inline comments";
+    CycleLineNumbers => "cycle-line-numbers", "View", "Cycle line numbers";
+    CycleTheme => "cycle-theme", "View", "Cycle color theme";
+    Help => "help", "View", "Show this help";
+    CommandLine => "command-line", "File", "Command line (Tab completes)";
+    Export => "export", "File", "Export annotations as JSON";
+    ExportPrompt => "export-prompt", "File", "Copy prompt to clipboard";
+    PreviewPrompt => "preview-prompt", "File", "Preview prompt";
+    Send => "send", "File", "Pipe prompt to the send command";
+    ShowOutput => "show-output", "File", "Show send command output";
+    CopyJson => "copy-json", "File", "Copy JSON export to clipboard";
+    CopyAnnotation => "copy-annotation", "File", "Copy selected annotation";
+    OpenFile => "open-file", "File", "Open file";
+    LoadResponse => "load-response", "File", "Review revisions from a model response";
+    LoadRevision => "load-revision", "File", "Compare with a revised version";
+    Quit => "quit", "File", "Quit";
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::all()
+            .iter()
+            .copied()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| format!("Unknown action: {}", s))
+    }
+}
+
+/// User key overrides per context, as chord -> action name.
+/// An action name of `none` removes the binding.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct KeyOverrides {
+    #[serde(default)]
+    pub normal: BTreeMap<String, String>,
+    #[serde(default)]
+    pub visual: BTreeMap<String, String>,
+}
+
+/// A single key binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub context: KeyContext,
+    pub chord: KeyChord,
+    pub action: Action,
+}
+
+/// Registry of key bindings
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    /// Look up the action bound to a chord
+    pub fn lookup(&self, context: KeyContext, chord: KeyChord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|b| b.context == context && b.chord == chord)
+            .map(|b| b.action)
+    }
+
+    /// Bind a chord, replacing any existing binding for it
+    pub fn bind(&mut self, context: KeyContext, chord: KeyChord, action: Action) {
+        self.unbind(context, chord);
+        self.bindings.push(Binding {
+            context,
+            chord,
+            action,
+        });
+    }
+
+    pub fn unbind(&mut self, context: KeyContext, chord: KeyChord) {
+        self.bindings
+            .retain(|b| !(b.context == context && b.chord == chord));
+    }
+
+    /// Chords bound to an action, in binding order
+    pub fn chords_for(&self, context: KeyContext, action: Action) -> Vec<KeyChord> {
+        self.bindings
+            .iter()
+            .filter(|b| b.context == context && b.action == action)
+            .map(|b| b.chord)
+            .collect()
+    }
+
+    /// Apply user overrides, returning a message for each entry that could not be applied
+    pub fn apply_overrides(&mut self, overrides: &KeyOverrides) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (context, entries) in [
+            (KeyContext::Normal, &overrides.normal),
+            (KeyContext::Visual, &overrides.visual),
+        ] {
+            for (chord, action) in entries {
+                let chord = match chord.parse::<KeyChord>() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
+                    }
+                };
+                if action == "none" {
+                    self.unbind(context, chord);
+                    continue;
+                }
+                match action.parse::<Action>() {
+                    Ok(action) => self.bind(context, chord, action),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+        errors
+    }
+
+    /// Bound actions grouped by help section, with their keys joined for display
+    pub fn help_sections(&self) -> Vec<(&'static str, Vec<(String, Action)>)> {
+        let mut sections: Vec<(&'static str, Vec<(String, Action)>)> = Vec::new();
+        for action in Action::all() {
+            let mut chords = self.chords_for(KeyContext::Normal, *action);
+            if chords.is_empty() {
+                chords = self.chords_for(KeyContext::Visual, *action);
+            }
+            if chords.is_empty() {
+                continue;
+            }
+
+            let keys = chords
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let entry = (keys, *action);
+            match sections
+                .iter_mut()
+                .find(|(name, _)| *name == action.section())
+            {
+                Some((_, entries)) => entries.push(entry),
+                None => sections.push((action.section(), vec![entry])),
+            }
+        }
+        sections
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+        use KeyContext::{Normal, Visual};
+
+        let normal: &[(Key, Action)] = &[
+            (Key::Char('q'), Quit),
+            (Key::Char('?'), Help),
+            (Key::Char('j'), MoveDown),
+            (Key::Down, MoveDown),
+            (Key::Char('k'), MoveUp),
+            (Key::Up, MoveUp),
+            (Key::Char('h'), MoveLeft),
+            (Key::Left, MoveLeft),
+            (Key::Char('l'), MoveRight),
+            (Key::Right, MoveRight),
+            (Key::Char('g'), MoveTop),
+            (Key::Char('G'), MoveBottom),
+            (Key::Char(']'), NextAnnotation),
+            (Key::Char('['), PrevAnnotation),
+            (Key::Char('v'), VisualMode),
+            (Key::Char('a'), AnnotatePoint),
+            (Key::Char('m'), CommentSection),
+            (Key::Char('M'), CommentDocument),
+            (Key::Char('d'), DeleteAnnotation),
+            (Key::Char('r'), ToggleResolved),
+            (Key::Char('i'), ShowDetails),
+            (Key::Char('c'), Suggest),
+            (Key::Char('A'), ApplySuggestion),
+            (Key::Char('I'), CycleInlineComments),
+            (Key::Char('N'), CycleLineNumbers),
+            (Key::Char('T'), CycleTheme),
+            (Key::Enter, Select),
+            (Key::Char('H'), ToggleHideResolved),
+            (Key::Char('S'), CycleSeverityFilter),
+            (Key::Char('C'), CycleCategoryFilter),
+            (Key::Char('X'), ClearFilter),
+            (Key::Char('F'), ToggleExportScope),
+            (Key::Char('/'), Search),
+            (Key::Char('s'), CycleSort),
+            (Key::Char('='), ToggleGrouping),
+            (Key::Char('z'), CollapseGroup),
+            (Key::Char('Z'), ExpandGroups),
+            (Key::Tab, ToggleFocus),
+            (Key::Char('O'), ToggleOutline),
+            (Key::Char(':'), CommandLine),
+            (Key::Char('e'), Export),
+            (Key::Char('E'), ExportPrompt),
+            (Key::Char('p'), PreviewPrompt),
+            (Key::Char('P'), Send),
+            (Key::Char('Y'), CopyJson),
+            (Key::Char('y'), CopyAnnotation),
+            (Key::Char('o'), OpenFile),
+            (Key::Char('R'), LoadResponse),
+            (Key::Char('D'), LoadRevision),
+        ];
+        let visual: &[(Key, Action)] = &[
+            (Key::Esc, Cancel),
+            (Key::Char('j'), MoveDown),
+            (Key::Down, MoveDown),
+            (Key::Char('k'), MoveUp),
+            (Key::Up, MoveUp),
+            (Key::Char('h'), MoveLeft),
+            (Key::Left, MoveLeft),
+            (Key::Char('l'), MoveRight),
+            (Key::Right, MoveRight),
+            (Key::Char('w'), WordForward),
+            (Key::Char('b'), WordBack),
+            (Key::Char('a'), AddAnnotation),
+        ];
+
+        let bindings = normal
+            .iter()
+            .map(|(key, action)| (Normal, *key, *action))
+            .chain(visual.iter().map(|(key, action)| (Visual, *key, *action)))
+            .map(|(context, key, action)| Binding {
+                context,
+                chord: KeyChord::new(key),
+                action,
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chords() {
+        assert_eq!("j".parse(), Ok(KeyChord::new(Key::Char('j'))));
+        assert_eq!("ctrl+s".parse(), Ok(KeyChord::ctrl(Key::Char('s'))));
+        assert_eq!("Enter".parse(), Ok(KeyChord::new(Key::Enter)));
+        assert_eq!("F5".parse(), Ok(KeyChord::new(Key::F(5))));
+        assert!("nope".parse::<KeyChord>().is_err());
+        assert_eq!(KeyChord::ctrl(Key::Down).to_string(), "Ctrl+Down");
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut keymap = Keymap::default();
+        let mut overrides = KeyOverrides::default();
+        overrides
+            .normal
+            .insert("ctrl+s".to_string(), "export".to_string());
+        overrides.normal.insert("d".to_string(), "none".to_string());
+        overrides
+            .normal
+            .insert("x".to_string(), "explode".to_string());
+
+        let errors = keymap.apply_overrides(&overrides);
+        assert_eq!(errors, vec!["Unknown action: explode".to_string()]);
+        assert_eq!(
+            keymap.lookup(KeyContext::Normal, KeyChord::ctrl(Key::Char('s'))),
+            Some(Action::Export)
+        );
+        assert_eq!(
+            keymap.lookup(KeyContext::Normal, KeyChord::new(Key::Char('d'))),
+            None
+        );
+        assert_eq!(
+            keymap.lookup(KeyContext::Visual, KeyChord::new(Key::Char('j'))),
+            Some(Action::MoveDown)
+        );
+    }
+}
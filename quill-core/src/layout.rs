@@ -0,0 +1,171 @@
+/// A screen row in the wrapped editor view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayRow {
+    /// Source line, or `None` for virtual rows such as inline comments
+    pub line: Option<usize>,
+    /// Character column in the logical row where this screen row starts
+    pub start_col: usize,
+    /// Number of characters on this screen row
+    pub len: usize,
+}
+
+/// Mapping between logical rows and wrapped screen rows in the editor.
+///
+/// Frontends push each logical row as they render it, then use the layout
+/// to place the cursor, scroll, and translate screen positions back to
+/// cursor positions.
+#[derive(Debug, Clone, Default)]
+pub struct EditorLayout {
+    pub rows: Vec<DisplayRow>,
+    pub width: usize,
+    /// Display-only cells as (line, column of the character they precede)
+    pub extra_cells: Vec<(usize, usize)>,
+}
+
+impl EditorLayout {
+    pub fn new(width: usize) -> Self {
+        Self {
+            rows: Vec::new(),
+            width: width.max(1),
+            extra_cells: Vec::new(),
+        }
+    }
+
+    /// Record a cell drawn before character `col` of `line` that isn't part
+    /// of the text, such as an insertion caret
+    pub fn insert_cell(&mut self, line: usize, col: usize) {
+        self.extra_cells.push((line, col));
+    }
+
+    /// Columns of the extra cells on a line, in order
+    fn extra_cols(&self, line: usize) -> Vec<usize> {
+        let mut cols: Vec<usize> = self
+            .extra_cells
+            .iter()
+            .filter(|(l, _)| *l == line)
+            .map(|(_, c)| *c)
+            .collect();
+        cols.sort_unstable();
+        cols
+    }
+
+    /// Append a logical row of `len` characters, wrapping it at the layout
+    /// width. Returns the range of screen rows it occupies.
+    pub fn push(&mut self, line: Option<usize>, len: usize) -> std::ops::Range<usize> {
+        let first = self.rows.len();
+        let mut start_col = 0;
+        loop {
+            let row_len = (len - start_col).min(self.width);
+            self.rows.push(DisplayRow {
+                line,
+                start_col,
+                len: row_len,
+            });
+            start_col += row_len;
+            if start_col >= len {
+                break;
+            }
+        }
+        first..self.rows.len()
+    }
+
+    /// First screen row of a source line
+    pub fn first_row(&self, line: usize) -> Option<usize> {
+        self.rows.iter().position(|r| r.line == Some(line))
+    }
+
+    /// Screen (row, column) of a cursor position
+    pub fn cursor_position(&self, line: usize, col: usize) -> Option<(usize, usize)> {
+        let first = self.first_row(line)?;
+        // The cursor sits on the character, after any cells drawn before it
+        let col = col + self.extra_cols(line).iter().filter(|c| **c <= col).count();
+        let mut found = (first, col);
+        for (i, row) in self.rows.iter().enumerate().skip(first) {
+            if row.line != Some(line) {
+                break;
+            }
+            found = (i, col.saturating_sub(row.start_col));
+            if col < row.start_col + row.len {
+                break;
+            }
+        }
+        Some(found)
+    }
+
+    /// Cursor (line, column) at a screen row and column, if the row shows source text
+    pub fn position_at(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+        let display = self.rows.get(row)?;
+        let line = display.line?;
+        let cell = display.start_col + col.min(display.len);
+        // Cells before this one that aren't text; an extra cell maps to the
+        // character it precedes
+        let skipped = self
+            .extra_cols(line)
+            .iter()
+            .enumerate()
+            .filter(|(i, c)| *c + i < cell)
+            .count();
+        Some((line, cell - skipped))
+    }
+}
+
+/// Lines moved by PageUp/PageDown in the full-screen text panes
+pub const PAGE_LINES: usize = 20;
+
+/// First visible line after scrolling `delta` lines from `scroll`, keeping
+/// at least the last of `line_count` lines on screen
+pub fn scroll_by(scroll: usize, delta: isize, line_count: usize) -> usize {
+    scroll
+        .saturating_add_signed(delta)
+        .min(line_count.saturating_sub(1))
+}
+
+/// Scroll offset that keeps `row` visible in a view `height` rows tall
+pub fn scroll_offset(row: usize, height: usize) -> usize {
+    if row >= height {
+        row - height + 1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrapping_and_cursor_mapping() {
+        let mut layout = EditorLayout::new(4);
+        assert_eq!(layout.push(Some(0), 10), 0..3);
+        assert_eq!(layout.push(None, 2), 3..4);
+        assert_eq!(layout.push(Some(1), 0), 4..5);
+
+        assert_eq!(layout.cursor_position(0, 5), Some((1, 1)));
+        // End of line stays on the last wrapped row
+        assert_eq!(layout.cursor_position(0, 10), Some((2, 2)));
+        assert_eq!(layout.cursor_position(1, 0), Some((4, 0)));
+
+        assert_eq!(layout.position_at(2, 1), Some((0, 9)));
+        assert_eq!(layout.position_at(2, 99), Some((0, 10)));
+        assert_eq!(layout.position_at(3, 0), None);
+        assert_eq!(layout.first_row(1), Some(4));
+    }
+
+    #[test]
+    fn test_extra_cells_shift_cursor_mapping() {
+        // "abcd" with a caret before 'c' and one at the end: "ab‸cd‸"
+        let mut layout = EditorLayout::new(80);
+        layout.push(Some(0), 6);
+        layout.insert_cell(0, 2);
+        layout.insert_cell(0, 4);
+
+        assert_eq!(layout.cursor_position(0, 1), Some((0, 1)));
+        assert_eq!(layout.cursor_position(0, 2), Some((0, 3)));
+        assert_eq!(layout.cursor_position(0, 4), Some((0, 6)));
+
+        assert_eq!(layout.position_at(0, 2), Some((0, 2)));
+        assert_eq!(layout.position_at(0, 3), Some((0, 2)));
+        assert_eq!(layout.position_at(0, 4), Some((0, 3)));
+        assert_eq!(layout.position_at(0, 6), Some((0, 4)));
+    }
+}
@@ -6,11 +6,50 @@
 
 pub mod actions;
 pub mod app;
+pub mod check;
+pub mod command;
+pub mod config;
 pub mod cursor;
+pub mod diff;
+pub mod dispatch;
+pub mod excerpt;
 pub mod export;
+pub mod filter;
+pub mod keymap;
+pub mod layout;
 pub mod model;
+pub mod outline;
+pub mod platform;
+pub mod preview;
+pub mod response;
+pub mod scrollbar;
+pub mod send;
+pub mod sidecar;
+pub mod sort;
+pub mod template;
+pub mod theme;
 
-pub use app::{App, Focus, InputTarget, Mode};
+pub use app::{App, Focus, GutterMode, InlineComments, InputTarget, Mode, SidebarRow};
+pub use check::{check, CheckReport, Thresholds};
+pub use config::Config;
 pub use cursor::CursorState;
-pub use export::{generate_prompt, to_json, ExportAnnotation, ExportDocument};
-pub use model::{Annotation, Category, Document, Severity, TextRange};
+pub use diff::{Hunk, Revision, Segment};
+pub use excerpt::ExcerptContext;
+pub use export::{
+    budget_warning, estimate_tokens, general_label, generate_prompt, generate_prompt_with,
+    insertion_point, join_prompts, section_title, to_json, ExportAnnotation, ExportDocument,
+    PromptOptions,
+};
+pub use filter::AnnotationFilter;
+pub use keymap::{Action, Key, KeyChord, KeyContext, Keymap};
+pub use layout::{DisplayRow, EditorLayout};
+pub use model::{Annotation, Category, Document, Scope, Severity, TextRange};
+pub use outline::{Heading, SeverityCounts};
+pub use platform::Platform;
+pub use preview::Preview;
+pub use response::{parse_response, Proposal, ProposalStatus, Review};
+pub use scrollbar::Scrollbar;
+pub use send::{SendConfig, SendFormat, SendOutput};
+pub use sort::SortOrder;
+pub use template::Template;
+pub use theme::{Theme, ThemeColor};
@@ -1,3 +1,6 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -39,11 +42,25 @@ impl Category {
     }
 }
 
+impl FromStr for Category {
+    type Err = String;
+
+    /// Parse a category name, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Category::all()
+            .iter()
+            .copied()
+            .find(|c| c.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown category: {}", s))
+    }
+}
+
 /// Severity level for annotations
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum Severity {
     MustFix,
+    #[default]
     ShouldFix,
     Consider,
 }
@@ -68,12 +85,77 @@ impl Severity {
             Severity::Consider => "CONSIDER",
         }
     }
+
+    /// Name as serialized, e.g. `must-fix`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::MustFix => "must-fix",
+            Severity::ShouldFix => "should-fix",
+            Severity::Consider => "consider",
+        }
+    }
+
+    /// Rank for ordering, most severe first
+    pub fn rank(&self) -> usize {
+        match self {
+            Severity::MustFix => 0,
+            Severity::ShouldFix => 1,
+            Severity::Consider => 2,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    /// Parse `must-fix`, `Must Fix` or `MUST` style names, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::all()
+            .iter()
+            .copied()
+            .find(|sev| {
+                [sev.name(), sev.as_str(), sev.short()]
+                    .iter()
+                    .any(|n| n.eq_ignore_ascii_case(s))
+            })
+            .ok_or_else(|| format!("Unknown severity: {}", s))
+    }
+}
+
+/// What an annotation comments on
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// The text in its range
+    #[default]
+    Range,
+    /// The section under the heading line its range covers
+    Section,
+    /// The whole document
+    Document,
 }
 
-impl Default for Severity {
-    fn default() -> Self {
-        Severity::ShouldFix
+impl Scope {
+    pub fn is_range(&self) -> bool {
+        *self == Scope::Range
     }
+
+    /// Rank for ordering, general comments first
+    pub fn rank(&self) -> usize {
+        match self {
+            Scope::Document => 0,
+            Scope::Section => 1,
+            Scope::Range => 2,
+        }
+    }
+}
+
+/// Text either side of an insertion point, saved so the point can be found
+/// again after the document changes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PointContext {
+    pub before: String,
+    pub after: String,
 }
 
 /// An annotation attached to a text range
@@ -87,8 +169,26 @@ pub struct Annotation {
     pub category: Option<Category>,
     pub severity: Severity,
     pub comment: String,
+    #[serde(default, skip_serializing_if = "Scope::is_range")]
+    pub scope: Scope,
+    /// Proposed replacement for the annotated text; empty suggests deleting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
     #[serde(default)]
     pub is_resolved: bool,
+    /// Creation time; the Unix epoch for annotations saved without one, so
+    /// they sort first and keep their order across loads
+    #[serde(default = "unknown_created_at")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Surrounding text of an insertion point, as of the last sidecar save
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<PointContext>,
+}
+
+fn unknown_created_at() -> DateTime<Utc> {
+    DateTime::UNIX_EPOCH
 }
 
 impl Annotation {
@@ -100,7 +200,31 @@ impl Annotation {
             category: None,
             severity: Severity::default(),
             comment,
+            scope: Scope::Range,
+            suggestion: None,
             is_resolved: false,
+            created_at: Utc::now(),
+            author: None,
+            context: None,
         }
     }
+
+    /// Whether the creation time wasn't saved with the annotation
+    pub fn created_unknown(&self) -> bool {
+        self.created_at == DateTime::UNIX_EPOCH
+    }
+
+    /// Whether this is a comment on a section or the whole document rather
+    /// than on the text in its range
+    pub fn is_general(&self) -> bool {
+        !self.scope.is_range()
+    }
+
+    /// Whether this has a suggestion to show; deleting nothing at an
+    /// insertion point is no suggestion
+    pub fn has_suggestion(&self) -> bool {
+        self.suggestion
+            .as_deref()
+            .is_some_and(|s| !s.is_empty() || !self.range.is_point())
+    }
 }
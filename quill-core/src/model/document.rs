@@ -2,7 +2,19 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::Annotation;
+use super::{Annotation, Severity, TextRange};
+use crate::sort::SortOrder;
+
+/// A stretch of text covered by the same set of annotations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub start_offset: usize,
+    pub end_offset: usize,
+    /// Most severe severity among the covering annotations
+    pub severity: Severity,
+    /// Number of annotations covering this stretch
+    pub count: usize,
+}
 
 /// A document with annotations
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +27,10 @@ pub struct Document {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filepath: Option<String>,
     pub annotations: Vec<Annotation>,
+    /// Sidecar annotations whose text could not be found in the document,
+    /// kept so saving the sidecar doesn't lose them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orphaned: Vec<Annotation>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -29,6 +45,7 @@ impl Document {
             filename: None,
             filepath: None,
             annotations: Vec::new(),
+            orphaned: Vec::new(),
             created_at: now,
             updated_at: now,
         }
@@ -46,6 +63,16 @@ impl Document {
         self.content.split_whitespace().count()
     }
 
+    /// 1-based line number containing a byte offset
+    pub fn line_number(&self, offset: usize) -> usize {
+        let end = offset.min(self.content.len());
+        self.content.as_bytes()[..end]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count()
+            + 1
+    }
+
     pub fn add_annotation(&mut self, annotation: Annotation) {
         self.annotations.push(annotation);
         self.updated_at = Utc::now();
@@ -70,10 +97,222 @@ impl Document {
         }
     }
 
+    /// Replace the text in `range`, shifting annotations after it and clamping
+    /// ones that overlap it to the new text; insertion points inside it move to
+    /// its start. Returns the range of the new text,
+    /// or None if `range` is not on character boundaries within the content.
+    pub fn replace_range(&mut self, range: &TextRange, text: &str) -> Option<TextRange> {
+        let (start, end) = (range.start_offset, range.end_offset);
+        self.content.get(start..end)?;
+        self.content.replace_range(start..end, text);
+        let new_end = start + text.len();
+
+        for ann in &mut self.annotations {
+            let (a_start, a_end) = (ann.range.start_offset, ann.range.end_offset);
+            // Text inserted where a range starts goes before it
+            let new_start = match a_start {
+                s if s < start => s,
+                s if s >= end => s - end + new_end,
+                _ => start,
+            };
+            let moved_end = match a_end {
+                _ if a_start == a_end => new_start,
+                e if e <= start => e,
+                e if e >= end => e - end + new_end,
+                _ => new_end,
+            };
+            ann.range = TextRange::new(new_start, moved_end);
+            // Text of annotations touching the edit changes with it
+            if a_start <= end && a_end >= start {
+                ann.selected_text = self.content[new_start..moved_end].to_string();
+            }
+        }
+        self.updated_at = Utc::now();
+        Some(TextRange::new(start, new_end))
+    }
+
+    /// Split the text around a range into (before, selected, after), with up to
+    /// `context` bytes on each side, snapped to character boundaries
+    pub fn excerpt(&self, range: &TextRange, context: usize) -> (&str, &str, &str) {
+        let content = self.content.as_str();
+        let start = floor_char_boundary(content, range.start_offset);
+        let end = floor_char_boundary(content, range.end_offset.max(start));
+        let before = floor_char_boundary(content, start.saturating_sub(context));
+        let after = floor_char_boundary(content, end + context);
+        (
+            &content[before..start],
+            &content[start..end],
+            &content[end..after],
+        )
+    }
+
+    /// Up to `count` words on each side of an offset, within its line
+    pub fn words_around(&self, offset: usize, count: usize) -> (String, String) {
+        let offset = floor_char_boundary(&self.content, offset);
+        let line_start = self.content[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.content[offset..]
+            .find('\n')
+            .map_or(self.content.len(), |i| offset + i);
+        let before: Vec<_> = self.content[line_start..offset]
+            .split_whitespace()
+            .collect();
+        let after: Vec<_> = self.content[offset..line_end]
+            .split_whitespace()
+            .take(count)
+            .collect();
+        (
+            before[before.len().saturating_sub(count)..].join(" "),
+            after.join(" "),
+        )
+    }
+
+    /// Get annotations containing the given offset, sorted by start offset
+    pub fn annotations_at(&self, offset: usize) -> Vec<&Annotation> {
+        self.annotations_sorted()
+            .into_iter()
+            .filter(|a| !a.is_general() && a.range.contains(offset))
+            .collect()
+    }
+
+    /// Split annotated text into non-overlapping stretches, sorted by offset,
+    /// each recording how many annotations cover it. General comments cover
+    /// no text.
+    pub fn coverage(&self) -> Vec<Coverage> {
+        let ranged: Vec<_> = self
+            .annotations
+            .iter()
+            .filter(|a| !a.is_general())
+            .collect();
+        let mut bounds: Vec<usize> = ranged
+            .iter()
+            .flat_map(|a| [a.range.start_offset, a.range.end_offset])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut stretches: Vec<Coverage> = Vec::new();
+        for window in bounds.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            let covering: Vec<_> = ranged
+                .iter()
+                .filter(|a| a.range.start_offset <= start && a.range.end_offset >= end)
+                .collect();
+            if covering.is_empty() {
+                continue;
+            }
+            let severity = covering
+                .iter()
+                .map(|a| a.severity)
+                .min_by_key(|s| s.rank())
+                .unwrap_or_default();
+            stretches.push(Coverage {
+                start_offset: start,
+                end_offset: end,
+                severity,
+                count: covering.len(),
+            });
+        }
+        stretches
+    }
+
+    /// Zero-length annotations marking where text should be inserted, sorted by offset
+    pub fn insertion_points(&self) -> Vec<&Annotation> {
+        self.annotations_sorted()
+            .into_iter()
+            .filter(|a| !a.is_general() && a.range.is_point())
+            .collect()
+    }
+
     /// Get annotations sorted by start offset
     pub fn annotations_sorted(&self) -> Vec<&Annotation> {
+        self.annotations_sorted_by(SortOrder::Position)
+    }
+
+    /// Get annotations sorted by the given order
+    pub fn annotations_sorted_by(&self, order: SortOrder) -> Vec<&Annotation> {
         let mut sorted: Vec<_> = self.annotations.iter().collect();
-        sorted.sort_by_key(|a| a.range.start_offset);
+        sorted.sort_by(|a, b| order.compare(a, b));
         sorted
     }
 }
+
+/// Largest char boundary at or before `index`, clamped to the string length
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    let mut index = index.min(s.len());
+    while !s.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excerpt_respects_char_boundaries() {
+        let doc = Document::new("Test".to_string(), "café au lait".to_string());
+        // 'é' spans bytes 3..5; context of 2 would land mid-character
+        let (before, selected, after) = doc.excerpt(&TextRange::new(6, 8), 2);
+        assert_eq!((before, selected, after), ("é ", "au", " l"));
+
+        let (before, _, after) = doc.excerpt(&TextRange::new(0, 4), 100);
+        assert_eq!(before, "");
+        assert_eq!(after, "é au lait");
+    }
+
+    #[test]
+    fn test_replace_range_shifts_annotations() {
+        let mut doc = Document::new("Test".to_string(), "one two three".to_string());
+        for (start, end) in [(0, 3), (4, 7), (8, 13), (0, 7)] {
+            let text = doc.content[start..end].to_string();
+            doc.add_annotation(Annotation::new(
+                TextRange::new(start, end),
+                text,
+                String::new(),
+            ));
+        }
+
+        let range = doc.replace_range(&TextRange::new(4, 7), "2").unwrap();
+        assert_eq!(range, TextRange::new(4, 5));
+        assert_eq!(doc.content, "one 2 three");
+        let texts: Vec<_> = doc
+            .annotations
+            .iter()
+            .map(|a| a.selected_text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["one", "2", "three", "one 2"]);
+        assert_eq!(doc.annotations[2].range, TextRange::new(6, 11));
+
+        assert!(doc.replace_range(&TextRange::new(4, 40), "x").is_none());
+    }
+
+    #[test]
+    fn test_coverage_of_overlapping_annotations() {
+        let mut doc = Document::new("Test".to_string(), "abcdefghij".to_string());
+        let mut must = Annotation::new(TextRange::new(4, 8), String::new(), String::new());
+        must.severity = Severity::MustFix;
+        doc.add_annotation(Annotation::new(
+            TextRange::new(0, 6),
+            String::new(),
+            String::new(),
+        ));
+        doc.add_annotation(must);
+
+        let spans: Vec<_> = doc
+            .coverage()
+            .iter()
+            .map(|c| (c.start_offset, c.end_offset, c.severity, c.count))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 4, Severity::ShouldFix, 1),
+                (4, 6, Severity::MustFix, 2),
+                (6, 8, Severity::MustFix, 1),
+            ]
+        );
+        assert_eq!(doc.annotations_at(5).len(), 2);
+        assert_eq!(doc.annotations_at(8).len(), 0);
+    }
+}
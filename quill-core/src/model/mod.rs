@@ -2,6 +2,6 @@ pub mod annotation;
 pub mod document;
 pub mod text_range;
 
-pub use annotation::{Annotation, Category, Severity};
-pub use document::Document;
+pub use annotation::{Annotation, Category, PointContext, Scope, Severity};
+pub use document::{Coverage, Document};
 pub use text_range::TextRange;
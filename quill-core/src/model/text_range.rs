@@ -16,8 +16,16 @@ impl TextRange {
         }
     }
 
-    /// Check if this range contains the given offset
+    /// Whether this is an insertion point rather than a span of text
+    pub fn is_point(&self) -> bool {
+        self.start_offset == self.end_offset
+    }
+
+    /// Check if this range contains the given offset; a point contains only its own
     pub fn contains(&self, offset: usize) -> bool {
+        if self.is_point() {
+            return offset == self.start_offset;
+        }
         offset >= self.start_offset && offset < self.end_offset
     }
 }
@@ -0,0 +1,163 @@
+use serde::Serialize;
+
+use crate::model::{Document, Scope, Severity, TextRange};
+
+/// A Markdown `#` heading and the extent of its section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// Heading level (1 for `#`, 2 for `##`, ...)
+    pub level: usize,
+    pub title: String,
+    /// Line index of the heading
+    pub line: usize,
+    /// Offset of the start of the heading line
+    pub start_offset: usize,
+    /// Offset where the section ends (next heading of same or higher level)
+    pub end_offset: usize,
+}
+
+impl Heading {
+    /// Check if the given offset falls within this heading's section
+    pub fn contains(&self, offset: usize) -> bool {
+        offset >= self.start_offset && offset < self.end_offset
+    }
+
+    /// Range of the heading line itself, which section comments are anchored to
+    pub fn line_range(&self, content: &str) -> TextRange {
+        let end = content[self.start_offset..]
+            .find('\n')
+            .map_or(content.len(), |i| self.start_offset + i);
+        TextRange::new(self.start_offset, end)
+    }
+}
+
+/// Unresolved annotation counts per severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeverityCounts {
+    pub must_fix: usize,
+    pub should_fix: usize,
+    pub consider: usize,
+}
+
+impl SeverityCounts {
+    pub fn get(&self, severity: Severity) -> usize {
+        match severity {
+            Severity::MustFix => self.must_fix,
+            Severity::ShouldFix => self.should_fix,
+            Severity::Consider => self.consider,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.must_fix + self.should_fix + self.consider
+    }
+}
+
+/// Parse ATX-style headings from Markdown content, skipping fenced code blocks
+pub fn parse_headings(content: &str) -> Vec<Heading> {
+    let mut headings: Vec<Heading> = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    for (line_idx, line) in content.split('\n').enumerate() {
+        let line_start = offset;
+        offset += line.len() + 1;
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if level == 0 || level > 6 {
+            continue;
+        }
+        let rest = &trimmed[level..];
+        if !rest.is_empty() && !rest.starts_with(' ') && !rest.starts_with('\t') {
+            continue;
+        }
+
+        let title = rest.trim().trim_end_matches('#').trim_end().to_string();
+        headings.push(Heading {
+            level,
+            title,
+            line: line_idx,
+            start_offset: line_start,
+            end_offset: content.len(),
+        });
+    }
+
+    // Each section runs until the next heading at the same or a higher level
+    for i in 0..headings.len() {
+        let level = headings[i].level;
+        if let Some(next) = headings[i + 1..].iter().find(|h| h.level <= level) {
+            headings[i].end_offset = next.start_offset;
+        }
+    }
+
+    headings
+}
+
+/// Count unresolved annotations starting within a heading's section
+pub fn section_counts(doc: &Document, heading: &Heading) -> SeverityCounts {
+    let mut counts = SeverityCounts::default();
+    for ann in doc.annotations.iter().filter(|a| {
+        !a.is_resolved && a.scope != Scope::Document && heading.contains(a.range.start_offset)
+    }) {
+        match ann.severity {
+            Severity::MustFix => counts.must_fix += 1,
+            Severity::ShouldFix => counts.should_fix += 1,
+            Severity::Consider => counts.consider += 1,
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Annotation, TextRange};
+
+    #[test]
+    fn test_parse_headings() {
+        let content =
+            "# Title\nintro\n## One\ntext\n```\n# not a heading\n```\n## Two\nmore\n#nospace";
+        let headings = parse_headings(content);
+
+        let titles: Vec<_> = headings
+            .iter()
+            .map(|h| (h.level, h.title.as_str()))
+            .collect();
+        assert_eq!(titles, vec![(1, "Title"), (2, "One"), (2, "Two")]);
+
+        assert_eq!(headings[0].line, 0);
+        assert_eq!(headings[0].end_offset, content.len());
+        assert_eq!(headings[1].start_offset, 14);
+        assert_eq!(headings[1].end_offset, headings[2].start_offset);
+    }
+
+    #[test]
+    fn test_section_counts() {
+        let content = "# A\naaaa\n# B\nbbbb";
+        let mut doc = Document::new("Test".to_string(), content.to_string());
+        let mut must = Annotation::new(TextRange::new(4, 6), "aa".to_string(), String::new());
+        must.severity = Severity::MustFix;
+        doc.add_annotation(must);
+        doc.add_annotation(Annotation::new(
+            TextRange::new(13, 15),
+            "bb".to_string(),
+            String::new(),
+        ));
+
+        let headings = parse_headings(content);
+        let a = section_counts(&doc, &headings[0]);
+        let b = section_counts(&doc, &headings[1]);
+        assert_eq!((a.must_fix, a.total()), (1, 1));
+        assert_eq!((b.should_fix, b.total()), (1, 1));
+    }
+}
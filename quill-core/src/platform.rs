@@ -0,0 +1,65 @@
+use anyhow::{bail, Result};
+
+use crate::model::Document;
+
+/// I/O that each frontend provides to the shared dispatcher
+pub trait Platform {
+    /// Load a document from a path typed by the user
+    fn open_file(&mut self, path: &str) -> Result<Document>;
+
+    /// Show the platform's own file picker, which loads the chosen document
+    /// later. Returns false to prompt for a path instead.
+    fn pick_file(&mut self) -> bool {
+        false
+    }
+
+    /// Read a text file, such as a model response
+    fn read_text(&mut self, _path: &str) -> Result<String> {
+        bail!("Reading files is not supported")
+    }
+
+    /// Show the platform's own file picker for a model response, which is
+    /// loaded later. Returns false to prompt for a path instead.
+    fn pick_response(&mut self) -> bool {
+        false
+    }
+
+    /// Show the platform's own file picker for a revised version of the
+    /// document, which is compared later. Returns false to prompt for a path
+    /// instead.
+    fn pick_revision(&mut self) -> bool {
+        false
+    }
+
+    /// Save exported content, returning a description of where it went
+    fn save_export(&mut self, filename: &str, contents: &str) -> Result<String>;
+
+    /// Persist a document's annotations alongside it, returning where they went
+    fn save_sidecar(&mut self, doc: &Document) -> Result<String>;
+
+    /// Write edited document text back, returning where it went
+    fn save_document(&mut self, _doc: &Document) -> Result<String> {
+        bail!("Saving documents is not supported")
+    }
+
+    /// Start a shell command with `input` on its stdin. Output is delivered
+    /// later through [`crate::App::push_output`] and
+    /// [`crate::App::finish_output`].
+    fn run_command(&mut self, _command: &str, _input: &str) -> Result<()> {
+        bail!("Running commands is not supported")
+    }
+
+    /// Stop the running command, if any
+    fn cancel_command(&mut self) {}
+
+    /// Save a command's output next to the document, returning where it went
+    fn save_response(&mut self, _doc: &Document, _text: &str) -> Result<String> {
+        bail!("Saving output is not supported")
+    }
+
+    /// Copy text to the system clipboard, returning what happened to it,
+    /// such as `copied to clipboard`
+    fn copy_to_clipboard(&mut self, _text: &str) -> Result<String> {
+        bail!("Clipboard not available")
+    }
+}
@@ -0,0 +1,260 @@
+//! Full-screen prompt preview with scrolling and search
+
+use std::ops::Range;
+
+use crate::app::{App, Mode};
+use crate::dispatch::PROMPT_FILENAME;
+use crate::excerpt::ExcerptContext;
+use crate::export::{budget_warning, estimate_tokens, join_prompts};
+use crate::keymap::Key;
+use crate::layout::{self, PAGE_LINES};
+use crate::platform::Platform;
+
+/// Generated prompt being previewed
+#[derive(Debug, Clone, Default)]
+pub struct Preview {
+    pub text: String,
+    /// Number of prompts joined in the text
+    pub parts: usize,
+    /// Which prompts are still over the token budget, if any
+    pub over_budget: Option<String>,
+    /// First visible line
+    pub scroll: usize,
+    pub query: String,
+    /// Typing a search query
+    pub searching: bool,
+    /// Lines containing the query
+    pub matches: Vec<usize>,
+    pub match_index: usize,
+}
+
+impl Preview {
+    pub fn line_count(&self) -> usize {
+        self.text.lines().count()
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn token_estimate(&self) -> usize {
+        estimate_tokens(&self.text)
+    }
+
+    /// Byte ranges of the query within a line
+    pub fn highlights(&self, line: &str) -> Vec<Range<usize>> {
+        find_in_line(line, &self.query)
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.scroll = layout::scroll_by(self.scroll, delta, self.line_count());
+    }
+
+    fn update_matches(&mut self) {
+        self.matches = self
+            .text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !find_in_line(line, &self.query).is_empty())
+            .map(|(i, _)| i)
+            .collect();
+        self.match_index = 0;
+    }
+
+    /// Scroll to the next (or previous) match after the current scroll position,
+    /// or at it when `include_current` is set
+    fn jump_to_match(&mut self, forward: bool, include_current: bool) -> bool {
+        if self.matches.is_empty() {
+            return false;
+        }
+        let from = self.scroll + usize::from(!include_current);
+        let index = if forward {
+            self.matches.iter().position(|&line| line >= from)
+        } else {
+            self.matches.iter().rposition(|&line| line < self.scroll)
+        };
+        // Wrap around the ends
+        self.match_index = index.unwrap_or(if forward { 0 } else { self.matches.len() - 1 });
+        self.scroll = self.matches[self.match_index];
+        true
+    }
+}
+
+/// Case-insensitive (ASCII) occurrences of `query` in `line`
+pub fn find_in_line(line: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let haystack = line.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, _)| start..start + needle.len())
+        .collect()
+}
+
+impl App {
+    /// Show the generated prompt full-screen
+    pub fn open_preview(&mut self) {
+        let Some(parts) = self.prompt_parts() else {
+            self.set_status("No document loaded");
+            return;
+        };
+        self.preview = Preview {
+            text: join_prompts(&parts),
+            parts: parts.len(),
+            over_budget: budget_warning(&parts, self.prompt_options.token_budget),
+            ..Preview::default()
+        };
+        self.mode = Mode::Preview;
+    }
+
+    /// Rebuild the preview after changing prompt options, keeping the search
+    fn regenerate_preview(&mut self) {
+        if let Some(parts) = self.prompt_parts() {
+            self.preview.text = join_prompts(&parts);
+            self.preview.parts = parts.len();
+            self.preview.over_budget = budget_warning(&parts, self.prompt_options.token_budget);
+            self.preview.scroll_by(0);
+            self.preview.update_matches();
+        }
+    }
+
+    pub(crate) fn handle_preview_key(&mut self, key: Key, platform: &mut dyn Platform) {
+        if self.preview.searching {
+            self.handle_preview_search_key(key);
+            return;
+        }
+
+        match key {
+            Key::Esc | Key::Char('q') | Key::Char('p') => self.mode = Mode::Normal,
+            Key::Char('j') | Key::Down => self.preview.scroll_by(1),
+            Key::Char('k') | Key::Up => self.preview.scroll_by(-1),
+            Key::PageDown | Key::Char(' ') => self.preview.scroll_by(PAGE_LINES as isize),
+            Key::PageUp => self.preview.scroll_by(-(PAGE_LINES as isize)),
+            Key::Char('g') | Key::Home => self.preview.scroll = 0,
+            Key::Char('G') | Key::End => self.preview.scroll_by(isize::MAX),
+            Key::Char('/') => {
+                self.preview.query.clear();
+                self.preview.searching = true;
+            }
+            Key::Char('n') => self.next_preview_match(true, false),
+            Key::Char('N') => self.next_preview_match(false, false),
+            Key::Char('y') => {
+                let text = self.preview.text.clone();
+                self.copy_text("Prompt", &text, platform);
+            }
+            Key::Char('w') => match platform.save_export(PROMPT_FILENAME, &self.preview.text) {
+                Ok(location) => self.set_status(&format!("Saved prompt to {}", location)),
+                Err(e) => self.set_status(&format!("Save failed: {}", e)),
+            },
+            Key::Char('f') => {
+                self.toggle_export_scoped();
+                self.regenerate_preview();
+            }
+            Key::Char('t') => {
+                self.prompt_options.full_text = !self.prompt_options.full_text;
+                self.regenerate_preview();
+                self.set_status(if self.prompt_options.full_text {
+                    "Prompt includes the full text"
+                } else {
+                    "Prompt omits the full text"
+                });
+            }
+            Key::Char('x') => {
+                self.prompt_options.excerpts = match self.prompt_options.excerpts {
+                    Some(_) => None,
+                    None => Some(ExcerptContext::default()),
+                };
+                self.regenerate_preview();
+                self.set_status(match self.prompt_options.excerpts {
+                    Some(_) => "Prompt includes excerpts around annotations",
+                    None => "Prompt includes the full text",
+                });
+            }
+            Key::Char('s') => {
+                self.prompt_options.structured = !self.prompt_options.structured;
+                self.regenerate_preview();
+                self.set_status(if self.prompt_options.structured {
+                    "Prompt asks for revisions as JSON"
+                } else {
+                    "Prompt asks for the revised document"
+                });
+            }
+            Key::Char('T') => {
+                self.cycle_template();
+                self.regenerate_preview();
+            }
+            Key::Char('r') => {
+                self.regenerate_preview();
+                self.set_status("Prompt regenerated");
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_preview_search_key(&mut self, key: Key) {
+        match key {
+            Key::Esc => {
+                self.preview.searching = false;
+                self.preview.query.clear();
+                self.preview.update_matches();
+            }
+            Key::Enter => {
+                self.preview.searching = false;
+                self.preview.update_matches();
+                self.next_preview_match(true, true);
+            }
+            Key::Backspace => {
+                self.preview.query.pop();
+            }
+            Key::Char(c) => self.preview.query.push(c),
+            _ => {}
+        }
+    }
+
+    fn next_preview_match(&mut self, forward: bool, include_current: bool) {
+        if self.preview.jump_to_match(forward, include_current) {
+            self.set_status(&format!(
+                "Match {}/{}",
+                self.preview.match_index + 1,
+                self.preview.matches.len()
+            ));
+        } else if !self.preview.query.is_empty() {
+            self.set_status(&format!("Not found: {}", self.preview.query));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Document;
+
+    #[test]
+    fn test_preview_search_and_options() {
+        let mut app = App::new();
+        app.load_document(Document::new(
+            "Notes".to_string(),
+            "alpha\nbeta\nalpha again".to_string(),
+        ));
+        app.open_preview();
+        assert_eq!(app.mode, Mode::Preview);
+        assert!(app.preview.text.contains("### Full Text"));
+
+        app.preview.query = "ALPHA".to_string();
+        app.preview.update_matches();
+        assert_eq!(app.preview.matches.len(), 2);
+        assert!(app.preview.jump_to_match(true, true));
+        let first = app.preview.scroll;
+        assert!(app.preview.jump_to_match(true, false));
+        assert!(app.preview.scroll > first);
+        assert_eq!(find_in_line("Alpha alpha", "alpha"), vec![0..5, 6..11]);
+
+        app.prompt_options.full_text = false;
+        app.regenerate_preview();
+        assert!(!app.preview.text.contains("### Full Text"));
+        assert!(app.preview.matches.is_empty());
+        assert!(app.preview.scroll < app.preview.line_count());
+    }
+}
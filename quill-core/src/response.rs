@@ -0,0 +1,361 @@
+//! Structured model responses: revisions keyed by annotation ID, reviewed
+//! one at a time and applied to the document
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::app::{App, Mode};
+use crate::keymap::Key;
+use crate::model::{Annotation, Document};
+
+/// Whether a proposed revision has been reviewed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Pending => "Pending",
+            ProposalStatus::Accepted => "Accepted",
+            ProposalStatus::Rejected => "Rejected",
+        }
+    }
+}
+
+/// A replacement for one annotation's text proposed by the model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proposal {
+    pub annotation_id: Uuid,
+    pub revised: String,
+    pub explanation: String,
+    pub status: ProposalStatus,
+}
+
+/// Proposals matched to a document's annotations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedResponse {
+    pub proposals: Vec<Proposal>,
+    /// IDs in the response that match no annotation
+    pub unmatched: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    id: String,
+    #[serde(
+        default,
+        alias = "revised_text",
+        alias = "revisedText",
+        alias = "replacement"
+    )]
+    revised: Option<String>,
+    #[serde(default, alias = "reason")]
+    explanation: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Body {
+    List(Vec<Entry>),
+    Wrapped {
+        #[serde(alias = "revisions", alias = "annotations")]
+        changes: Vec<Entry>,
+    },
+}
+
+/// Read revisions from a model response: a JSON array of `{id, revised,
+/// explanation}` objects, optionally in a fenced code block among other text.
+/// IDs may be shortened to any unique prefix.
+pub fn parse_response(doc: &Document, response: &str) -> Result<ParsedResponse, String> {
+    let json = json_block(response).ok_or("No JSON found in the response")?;
+    let entries =
+        match serde_json::from_str(json).map_err(|e| format!("Invalid response JSON: {}", e))? {
+            Body::List(entries) => entries,
+            Body::Wrapped { changes } => changes,
+        };
+
+    let mut parsed = ParsedResponse::default();
+    for entry in entries {
+        let Some(revised) = entry.revised else {
+            continue;
+        };
+        match find_annotation(doc, entry.id.trim()) {
+            Some(annotation_id)
+                if !parsed
+                    .proposals
+                    .iter()
+                    .any(|p| p.annotation_id == annotation_id) =>
+            {
+                parsed.proposals.push(Proposal {
+                    annotation_id,
+                    revised,
+                    explanation: entry.explanation,
+                    status: ProposalStatus::Pending,
+                });
+            }
+            Some(_) => {}
+            None => parsed.unmatched.push(entry.id),
+        }
+    }
+    // Review in document order
+    parsed
+        .proposals
+        .sort_by_key(|p| annotation(doc, p.annotation_id).map_or(0, |a| a.range.start_offset));
+    Ok(parsed)
+}
+
+/// The contents of the first ```json block, or else the outermost brackets
+fn json_block(response: &str) -> Option<&str> {
+    if let Some(start) = response.find("```json") {
+        let body = &response[start + "```json".len()..];
+        return Some(body[..body.find("```").unwrap_or(body.len())].trim());
+    }
+    let start = response.find(['[', '{'])?;
+    let end = response.rfind([']', '}'])?;
+    (end > start).then(|| &response[start..=end])
+}
+
+fn annotation(doc: &Document, id: Uuid) -> Option<&Annotation> {
+    doc.annotations.iter().find(|a| a.id == id)
+}
+
+/// The annotation an ID refers to; general comments have no text to revise
+fn find_annotation(doc: &Document, id: &str) -> Option<Uuid> {
+    if let Ok(uuid) = Uuid::parse_str(id) {
+        return annotation(doc, uuid)
+            .filter(|a| !a.is_general())
+            .map(|a| a.id);
+    }
+    if id.is_empty() {
+        return None;
+    }
+    let mut matches = doc
+        .annotations
+        .iter()
+        .filter(|a| !a.is_general() && a.id.to_string().starts_with(&id.to_ascii_lowercase()));
+    match (matches.next(), matches.next()) {
+        (Some(ann), None) => Some(ann.id),
+        _ => None,
+    }
+}
+
+/// Proposals being reviewed
+#[derive(Debug, Clone, Default)]
+pub struct Review {
+    pub proposals: Vec<Proposal>,
+    pub selected: usize,
+}
+
+impl Review {
+    pub fn current(&self) -> Option<&Proposal> {
+        self.proposals.get(self.selected)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.count(ProposalStatus::Pending)
+    }
+
+    pub fn count(&self, status: ProposalStatus) -> usize {
+        self.proposals.iter().filter(|p| p.status == status).count()
+    }
+
+    /// Select the next pending proposal after the current one, wrapping
+    fn next_pending(&mut self) {
+        let len = self.proposals.len();
+        if let Some(i) = (1..=len)
+            .map(|step| (self.selected + step) % len)
+            .find(|&i| self.proposals[i].status == ProposalStatus::Pending)
+        {
+            self.selected = i;
+        }
+    }
+}
+
+impl App {
+    /// Parse a model response and start reviewing its proposals
+    pub fn load_response(&mut self, response: &str) {
+        let Some(doc) = &self.document else {
+            self.set_status("No document loaded");
+            return;
+        };
+        let parsed = match parse_response(doc, response) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.set_status(&format!("Error: {}", e));
+                return;
+            }
+        };
+        if parsed.proposals.is_empty() {
+            self.set_status(&format!(
+                "No revisions match this document's annotations ({} unmatched)",
+                parsed.unmatched.len()
+            ));
+            return;
+        }
+
+        let mut status = format!("{} proposed revision(s)", parsed.proposals.len());
+        if !parsed.unmatched.is_empty() {
+            status.push_str(&format!(
+                ", {} unmatched ID(s) ignored",
+                parsed.unmatched.len()
+            ));
+        }
+        self.review = Review {
+            proposals: parsed.proposals,
+            selected: 0,
+        };
+        self.mode = Mode::Review;
+        self.set_status(&status);
+    }
+
+    /// Replace an annotation's text with a proposal's revision and resolve it.
+    /// Returns false if the annotation is gone or its text no longer matches.
+    fn apply_proposal(&mut self, index: usize) -> bool {
+        let Some(proposal) = self.review.proposals.get(index).cloned() else {
+            return false;
+        };
+        let Some(doc) = self.document.as_mut() else {
+            return false;
+        };
+        let Some(ann) = annotation(doc, proposal.annotation_id) else {
+            return false;
+        };
+        let range = ann.range.clone();
+        if doc.content.get(range.start_offset..range.end_offset) != Some(ann.selected_text.as_str())
+        {
+            return false;
+        }
+        if doc.replace_range(&range, &proposal.revised).is_none() {
+            return false;
+        }
+        if let Some(ann) = doc
+            .annotations
+            .iter_mut()
+            .find(|a| a.id == proposal.annotation_id)
+        {
+            ann.is_resolved = true;
+        }
+        self.content_changed();
+        true
+    }
+
+    fn accept_proposal(&mut self, index: usize) {
+        if self.apply_proposal(index) {
+            self.review.proposals[index].status = ProposalStatus::Accepted;
+        } else {
+            self.review.proposals[index].status = ProposalStatus::Rejected;
+            self.set_status("Annotated text has changed; revision skipped");
+        }
+    }
+
+    pub(crate) fn handle_review_key(&mut self, key: Key) {
+        if self.review.proposals.is_empty() {
+            self.mode = Mode::Normal;
+            return;
+        }
+        let selected = self.review.selected;
+        let pending = self.review.proposals[selected].status == ProposalStatus::Pending;
+
+        match key {
+            Key::Esc | Key::Char('q') => self.close_review(),
+            Key::Char('j') | Key::Down => {
+                self.review.selected = (selected + 1).min(self.review.proposals.len() - 1);
+            }
+            Key::Char('k') | Key::Up => self.review.selected = selected.saturating_sub(1),
+            Key::Char('a') | Key::Char('y') | Key::Enter if pending => {
+                self.accept_proposal(selected);
+                self.after_decision();
+            }
+            Key::Char('x') | Key::Char('n') if pending => {
+                self.review.proposals[selected].status = ProposalStatus::Rejected;
+                self.after_decision();
+            }
+            Key::Char('A') => {
+                for i in 0..self.review.proposals.len() {
+                    if self.review.proposals[i].status == ProposalStatus::Pending {
+                        self.accept_proposal(i);
+                    }
+                }
+                self.close_review();
+            }
+            _ => {}
+        }
+    }
+
+    fn after_decision(&mut self) {
+        if self.review.pending() == 0 {
+            self.close_review();
+        } else {
+            self.review.next_pending();
+        }
+    }
+
+    fn close_review(&mut self) {
+        let accepted = self.review.count(ProposalStatus::Accepted);
+        let rejected = self.review.count(ProposalStatus::Rejected);
+        let pending = self.review.pending();
+        self.mode = Mode::Normal;
+        let mut status = format!("Accepted {}, rejected {}", accepted, rejected);
+        if pending > 0 {
+            status.push_str(&format!(", {} left pending", pending));
+        }
+        if accepted > 0 {
+            status.push_str(" (:w to save)");
+        }
+        self.set_status(&status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TextRange;
+
+    #[test]
+    fn test_parse_and_accept_response() {
+        let mut doc = Document::new("Notes".to_string(), "The quick fox jumps.".to_string());
+        let quick = Annotation::new(
+            TextRange::new(4, 9),
+            "quick".to_string(),
+            "Stronger word".to_string(),
+        );
+        let jumps = Annotation::new(
+            TextRange::new(14, 19),
+            "jumps".to_string(),
+            "Tense".to_string(),
+        );
+        let (quick_id, jumps_id) = (quick.id, jumps.id);
+        doc.add_annotation(quick);
+        doc.add_annotation(jumps);
+
+        let response = format!(
+            "Here you go:\n\n```json\n[{{\"id\": \"{}\", \"revised\": \"jumped\", \"explanation\": \"Past tense\"}},\n {{\"id\": \"{}\", \"revised_text\": \"nimble\"}},\n {{\"id\": \"nope\", \"revised\": \"x\"}}]\n```\n",
+            jumps_id,
+            &quick_id.to_string()[..8]
+        );
+        let parsed = parse_response(&doc, &response).unwrap();
+        assert_eq!(parsed.unmatched, vec!["nope".to_string()]);
+        assert_eq!(parsed.proposals.len(), 2);
+        assert_eq!(parsed.proposals[0].annotation_id, quick_id);
+        assert_eq!(parsed.proposals[1].explanation, "Past tense");
+        assert!(parse_response(&doc, "no json here").is_err());
+
+        let mut app = App::new();
+        app.load_document(doc);
+        app.load_response(&response);
+        assert_eq!(app.mode, Mode::Review);
+        app.handle_review_key(Key::Char('a'));
+        app.handle_review_key(Key::Char('a'));
+        assert_eq!(app.mode, Mode::Normal);
+
+        let doc = app.document.as_ref().unwrap();
+        assert_eq!(doc.content, "The nimble fox jumped.");
+        assert!(doc.annotations.iter().all(|a| a.is_resolved));
+        assert_eq!(annotation(doc, jumps_id).unwrap().selected_text, "jumped");
+        assert!(app.content_modified);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::model::Severity;
+
+/// Vertical scrollbar track with viewport thumb and annotation ticks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scrollbar {
+    /// First track row covered by the thumb
+    pub thumb_start: usize,
+    /// Number of track rows covered by the thumb (at least 1)
+    pub thumb_len: usize,
+    /// Most severe annotation mapped onto each track row
+    pub ticks: Vec<Option<Severity>>,
+    total_rows: usize,
+}
+
+impl Scrollbar {
+    /// Create a track of `height` rows for `total_rows` of content scrolled by `scroll`
+    pub fn new(total_rows: usize, scroll: usize, height: usize) -> Self {
+        // Content shorter than the track maps one row to one track row
+        let scale = total_rows.max(height).max(1);
+        let thumb_start = (scroll * height / scale).min(height.saturating_sub(1));
+        let thumb_len = (height * height / scale)
+            .max(1)
+            .min(height.saturating_sub(thumb_start));
+        Self {
+            thumb_start,
+            thumb_len,
+            ticks: vec![None; height],
+            total_rows: scale,
+        }
+    }
+
+    /// Mark a content row with an annotation severity, keeping the most severe per track row
+    pub fn mark(&mut self, row: usize, severity: Severity) {
+        let height = self.ticks.len();
+        if height == 0 {
+            return;
+        }
+        let track_row = (row * height / self.total_rows).min(height - 1);
+        let tick = &mut self.ticks[track_row];
+        if tick.is_none_or(|current| severity.rank() < current.rank()) {
+            *tick = Some(severity);
+        }
+    }
+
+    /// Whether a track row is covered by the viewport thumb
+    pub fn in_thumb(&self, track_row: usize) -> bool {
+        track_row >= self.thumb_start && track_row < self.thumb_start + self.thumb_len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrollbar_thumb_and_ticks() {
+        let mut bar = Scrollbar::new(100, 50, 10);
+        assert_eq!((bar.thumb_start, bar.thumb_len), (5, 1));
+
+        bar.mark(95, Severity::Consider);
+        bar.mark(99, Severity::MustFix);
+        bar.mark(98, Severity::ShouldFix);
+        assert_eq!(bar.ticks[9], Some(Severity::MustFix));
+        assert!(bar.in_thumb(5) && !bar.in_thumb(6));
+
+        // Short content fills the track with the thumb
+        let bar = Scrollbar::new(3, 0, 10);
+        assert_eq!((bar.thumb_start, bar.thumb_len), (0, 10));
+    }
+}
@@ -0,0 +1,274 @@
+//! Piping the generated prompt to an external command and showing its output
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::app::{App, Mode};
+use crate::export::to_json;
+use crate::keymap::Key;
+use crate::layout::{self, PAGE_LINES};
+use crate::platform::Platform;
+
+/// Suffix appended to a document path to name the saved command output
+pub const RESPONSE_SUFFIX: &str = ".response.md";
+
+/// Saved output path for a document path
+pub fn response_path(doc_path: &str) -> String {
+    format!("{}{}", doc_path, RESPONSE_SUFFIX)
+}
+
+/// What the send action writes to the command's stdin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SendFormat {
+    /// The revision prompt from the current template
+    #[default]
+    Prompt,
+    /// The JSON export
+    Json,
+}
+
+impl fmt::Display for SendFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SendFormat::Prompt => "prompt",
+            SendFormat::Json => "json",
+        })
+    }
+}
+
+impl FromStr for SendFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "prompt" | "md" => Ok(SendFormat::Prompt),
+            "json" => Ok(SendFormat::Json),
+            _ => Err(format!("Unknown send format: {} (use prompt or json)", s)),
+        }
+    }
+}
+
+/// The `[send]` table of the config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SendConfig {
+    /// Shell command that reads the prompt on stdin, e.g. `llm -m gpt-4o`
+    pub command: Option<String>,
+    #[serde(default)]
+    pub format: SendFormat,
+}
+
+/// Output of the last command run by the send action
+#[derive(Debug, Clone, Default)]
+pub struct SendOutput {
+    pub command: String,
+    pub text: String,
+    pub running: bool,
+    /// Exit error, once finished
+    pub error: Option<String>,
+    /// Where the output was saved, once finished
+    pub saved_to: Option<String>,
+    /// First visible line, unless following
+    pub scroll: usize,
+    /// Show the last lines as output arrives, instead of from `scroll`
+    pub follow: bool,
+}
+
+impl SendOutput {
+    pub fn line_count(&self) -> usize {
+        self.text.lines().count()
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        if self.follow {
+            // Start from roughly the last page shown
+            self.scroll = self.line_count().saturating_sub(PAGE_LINES);
+            self.follow = false;
+        }
+        self.scroll = layout::scroll_by(self.scroll, delta, self.line_count());
+    }
+}
+
+impl App {
+    /// Pipe the prompt (or JSON export) to the configured command, replacing
+    /// it with `command` when given
+    pub fn start_send(&mut self, command: Option<&str>, platform: &mut dyn Platform) {
+        if let Some(command) = command {
+            self.send.command = Some(command.to_string());
+        }
+        let Some(command) = self.send.command.clone() else {
+            self.set_status("No send command configured (set [send] command, or :send COMMAND)");
+            return;
+        };
+        if self.output.running {
+            self.mode = Mode::Output;
+            self.set_status("A command is still running (c to cancel)");
+            return;
+        }
+
+        let input = match self.send.format {
+            SendFormat::Prompt => self.prompt_text(),
+            SendFormat::Json => match self.export_document().map(|doc| to_json(&doc)) {
+                Some(Ok(json)) => Some(json),
+                Some(Err(e)) => {
+                    self.set_status(&format!("Export failed: {}", e));
+                    return;
+                }
+                None => None,
+            },
+        };
+        let Some(input) = input else {
+            self.set_status("No document loaded");
+            return;
+        };
+
+        match platform.run_command(&command, &input) {
+            Ok(()) => {
+                self.output = SendOutput {
+                    command: command.clone(),
+                    running: true,
+                    follow: true,
+                    ..SendOutput::default()
+                };
+                self.mode = Mode::Output;
+                self.set_status(&format!("Sent {} to {}", self.send.format, command));
+            }
+            Err(e) => self.set_status(&format!("Send failed: {}", e)),
+        }
+    }
+
+    /// Append output from the running command
+    pub fn push_output(&mut self, chunk: &str) {
+        self.output.text.push_str(chunk);
+    }
+
+    /// Record that the command exited, saving its output next to the document
+    /// when it succeeded
+    pub fn finish_output(&mut self, error: Option<String>, platform: &mut dyn Platform) {
+        self.output.running = false;
+        if let Some(error) = error {
+            self.set_status(&format!("{} failed: {}", self.output.command, error));
+            self.output.error = Some(error);
+            return;
+        }
+
+        let Some(doc) = &self.document else {
+            return;
+        };
+        match platform.save_response(doc, &self.output.text) {
+            Ok(location) => {
+                self.set_status(&format!(
+                    "Saved output to {} (R to review revisions)",
+                    location
+                ));
+                self.output.saved_to = Some(location);
+            }
+            Err(e) => self.set_status(&format!("Command finished, but saving failed: {}", e)),
+        }
+    }
+
+    pub(crate) fn handle_output_key(&mut self, key: Key, platform: &mut dyn Platform) {
+        match key {
+            Key::Esc | Key::Char('q') => self.mode = Mode::Normal,
+            Key::Char('j') | Key::Down => self.output.scroll_by(1),
+            Key::Char('k') | Key::Up => self.output.scroll_by(-1),
+            Key::PageDown | Key::Char(' ') => self.output.scroll_by(PAGE_LINES as isize),
+            Key::PageUp => self.output.scroll_by(-(PAGE_LINES as isize)),
+            Key::Char('g') | Key::Home => self.output.scroll_by(isize::MIN),
+            Key::Char('G') | Key::End => self.output.follow = true,
+            Key::Char('c') if self.output.running => {
+                platform.cancel_command();
+                self.set_status("Cancelling...");
+            }
+            Key::Char('y') => {
+                let text = self.output.text.clone();
+                self.copy_text("Output", &text, platform);
+            }
+            Key::Char('R') if !self.output.running => {
+                let text = self.output.text.clone();
+                self.mode = Mode::Normal;
+                self.load_response(&text);
+            }
+            Key::Char('D') if !self.output.running => {
+                let text = self.output.text.clone();
+                let name = self.output.command.clone();
+                self.mode = Mode::Normal;
+                self.load_revision(&name, text);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Document;
+    use anyhow::Result;
+
+    /// Platform whose command echoes its input back in upper case
+    #[derive(Default)]
+    struct MockCommand {
+        input: Option<String>,
+        saved: Option<String>,
+    }
+
+    impl Platform for MockCommand {
+        fn open_file(&mut self, _path: &str) -> Result<Document> {
+            anyhow::bail!("not supported")
+        }
+
+        fn save_export(&mut self, filename: &str, _contents: &str) -> Result<String> {
+            Ok(filename.to_string())
+        }
+
+        fn save_sidecar(&mut self, _doc: &Document) -> Result<String> {
+            Ok(String::new())
+        }
+
+        fn run_command(&mut self, _command: &str, input: &str) -> Result<()> {
+            self.input = Some(input.to_string());
+            Ok(())
+        }
+
+        fn save_response(&mut self, doc: &Document, text: &str) -> Result<String> {
+            self.saved = Some(text.to_string());
+            Ok(response_path(doc.filepath.as_deref().unwrap_or(&doc.title)))
+        }
+    }
+
+    #[test]
+    fn test_send_streams_and_saves_output() {
+        let mut app = App::new();
+        let mut platform = MockCommand::default();
+        app.start_send(None, &mut platform);
+        assert!(app
+            .status_message
+            .as_deref()
+            .unwrap()
+            .starts_with("No send command"));
+
+        app.load_document(Document::new("Notes".to_string(), "Some text".to_string()));
+        app.start_send(Some("upcase"), &mut platform);
+        assert_eq!(app.mode, Mode::Output);
+        assert_eq!(platform.input, app.prompt_text());
+
+        let output = platform.input.clone().unwrap().to_uppercase();
+        let (first, rest) = output.split_at(10);
+        app.push_output(first);
+        app.push_output(rest);
+        assert!(app.output.running);
+        assert_eq!(app.output.text, output);
+
+        app.finish_output(None, &mut platform);
+        assert!(!app.output.running);
+        assert_eq!(platform.saved.as_deref(), Some(output.as_str()));
+        assert_eq!(app.output.saved_to.as_deref(), Some("Notes.response.md"));
+
+        app.send.format = SendFormat::Json;
+        app.start_send(None, &mut platform);
+        assert!(platform.input.unwrap().contains("\"title\": \"Notes\""));
+    }
+}
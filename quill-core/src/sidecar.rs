@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Annotation, Document, PointContext, Scope, TextRange};
+use crate::outline::{parse_headings, Heading};
+
+/// Suffix appended to a document path to name its sidecar file
+pub const SIDECAR_SUFFIX: &str = ".quill.json";
+
+/// Characters of text saved either side of an insertion point
+const POINT_CONTEXT_CHARS: usize = 20;
+
+/// Annotations persisted next to the document they belong to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sidecar {
+    pub annotations: Vec<Annotation>,
+}
+
+/// Sidecar path for a document path
+pub fn sidecar_path(doc_path: &str) -> String {
+    format!("{}{}", doc_path, SIDECAR_SUFFIX)
+}
+
+/// Serialize a document's annotations for its sidecar, including orphaned
+/// ones so they survive until their text is found again. Insertion points
+/// record the text around them.
+pub fn to_sidecar_json(doc: &Document) -> serde_json::Result<String> {
+    let annotations = doc.annotations.iter().map(|ann| {
+        let mut ann = ann.clone();
+        if ann.range.is_point() && ann.scope.is_range() {
+            ann.context = Some(point_context(&doc.content, ann.range.start_offset));
+        }
+        ann
+    });
+    serde_json::to_string_pretty(&Sidecar {
+        annotations: annotations.chain(doc.orphaned.iter().cloned()).collect(),
+    })
+}
+
+/// Text either side of `offset`, up to `POINT_CONTEXT_CHARS` characters each
+fn point_context(content: &str, offset: usize) -> PointContext {
+    let (before, after) = content.split_at(offset);
+    let start = before
+        .char_indices()
+        .rev()
+        .nth(POINT_CONTEXT_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    let end = after
+        .char_indices()
+        .nth(POINT_CONTEXT_CHARS)
+        .map_or(after.len(), |(i, _)| i);
+    PointContext {
+        before: before[start..].to_string(),
+        after: after[..end].to_string(),
+    }
+}
+
+/// Load annotations from sidecar JSON into a document, replacing any it has.
+///
+/// Annotations whose range no longer matches the document text move to the
+/// nearest place their text appears, insertion points to the nearest place
+/// their surrounding text appears, and section comments follow their
+/// heading line. Those whose text is gone are kept in `doc.orphaned`;
+/// returns how many.
+pub fn apply_sidecar(doc: &mut Document, json: &str) -> serde_json::Result<usize> {
+    let sidecar: Sidecar = serde_json::from_str(json)?;
+    let headings = parse_headings(&doc.content);
+    doc.annotations.clear();
+    doc.orphaned.clear();
+    for mut ann in sidecar.annotations {
+        match relocate(&doc.content, &headings, &ann) {
+            Some(range) => {
+                ann.range = range;
+                doc.annotations.push(ann);
+            }
+            None => doc.orphaned.push(ann),
+        }
+    }
+    Ok(doc.orphaned.len())
+}
+
+/// Where an annotation's text is in `content` now, if anywhere
+fn relocate(content: &str, headings: &[Heading], ann: &Annotation) -> Option<TextRange> {
+    let range = &ann.range;
+    if range.is_point() && ann.scope.is_range() {
+        return relocate_point(content, ann);
+    }
+    if content.get(range.start_offset..range.end_offset) == Some(ann.selected_text.as_str()) {
+        return Some(range.clone());
+    }
+    if ann.scope == Scope::Section {
+        return headings
+            .iter()
+            .map(|h| h.line_range(content))
+            .find(|r| content[r.start_offset..r.end_offset] == ann.selected_text);
+    }
+    if ann.selected_text.is_empty() {
+        return None;
+    }
+    content
+        .match_indices(&ann.selected_text)
+        .map(|(start, _)| start)
+        .min_by_key(|start| start.abs_diff(range.start_offset))
+        .map(|start| TextRange::new(start, start + ann.selected_text.len()))
+}
+
+/// Where an insertion point is in `content` now, found by the text around it
+fn relocate_point(content: &str, ann: &Annotation) -> Option<TextRange> {
+    let offset = ann.range.start_offset;
+    let Some(context) = &ann.context else {
+        // Saved without context; all we can check is that the offset is valid
+        return content.is_char_boundary(offset).then(|| ann.range.clone());
+    };
+    if content
+        .get(..offset)
+        .is_some_and(|before| before.ends_with(&context.before))
+        && content
+            .get(offset..)
+            .is_some_and(|after| after.starts_with(&context.after))
+    {
+        return Some(ann.range.clone());
+    }
+    content
+        .match_indices(&format!("{}{}", context.before, context.after))
+        .map(|(start, _)| start + context.before.len())
+        .min_by_key(|point| point.abs_diff(offset))
+        .map(|point| TextRange::new(point, point))
+}
+
+/// Warning for annotations that could not be restored, if there are any
+pub fn orphaned_warning(doc: &Document) -> Option<String> {
+    match doc.orphaned.len() {
+        0 => None,
+        1 => Some("1 annotation no longer matches the text (kept in the sidecar)".to_string()),
+        n => Some(format!(
+            "{} annotations no longer match the text (kept in the sidecar)",
+            n
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_round_trip_keeps_stale() {
+        let mut doc = Document::new("Test".to_string(), "Hello world".to_string());
+        doc.add_annotation(Annotation::new(
+            TextRange::new(0, 5),
+            "Hello".to_string(),
+            "Greeting".to_string(),
+        ));
+        doc.add_annotation(Annotation::new(
+            TextRange::new(6, 11),
+            "there".to_string(),
+            "Stale".to_string(),
+        ));
+        let json = to_sidecar_json(&doc).unwrap();
+
+        let mut reloaded = Document::new("Test".to_string(), "Hello world".to_string());
+        assert_eq!(apply_sidecar(&mut reloaded, &json).unwrap(), 1);
+        assert_eq!(reloaded.annotations.len(), 1);
+        assert_eq!(reloaded.annotations[0].comment, "Greeting");
+        assert_eq!(reloaded.orphaned[0].comment, "Stale");
+        assert!(orphaned_warning(&reloaded)
+            .unwrap()
+            .starts_with("1 annotation no longer"));
+        assert_eq!(sidecar_path("/tmp/a.md"), "/tmp/a.md.quill.json");
+
+        // Saving again keeps the orphan
+        let resaved = to_sidecar_json(&reloaded).unwrap();
+        let mut again = Document::new("Test".to_string(), "Hello there".to_string());
+        assert_eq!(apply_sidecar(&mut again, &resaved).unwrap(), 0);
+        assert_eq!(again.annotations.len(), 2);
+
+        // Edited text moves an annotation to where its text now is
+        let mut edited = Document::new("Test".to_string(), "Hi world, world".to_string());
+        doc.annotations = vec![Annotation::new(
+            TextRange::new(6, 11),
+            "world".to_string(),
+            "Moved".to_string(),
+        )];
+        assert_eq!(
+            apply_sidecar(&mut edited, &to_sidecar_json(&doc).unwrap()).unwrap(),
+            0
+        );
+        assert_eq!(edited.annotations[0].range, TextRange::new(3, 8));
+
+        // A section comment follows its heading when text is added above it
+        let mut section = Annotation::new(
+            TextRange::new(6, 12),
+            "## Two".to_string(),
+            "Move up".to_string(),
+        );
+        section.scope = Scope::Section;
+        doc.annotations = vec![section];
+        let json = to_sidecar_json(&doc).unwrap();
+        let mut edited = Document::new("Test".to_string(), "Intro\nmore\n## Two\n".to_string());
+        assert_eq!(apply_sidecar(&mut edited, &json).unwrap(), 0);
+        assert_eq!(edited.annotations[0].range, TextRange::new(11, 17));
+    }
+
+    #[test]
+    fn test_sidecar_relocates_points() {
+        let mut doc = Document::new("Test".to_string(), "one two\nthree".to_string());
+        doc.add_annotation(Annotation::new(
+            TextRange::new(4, 4),
+            String::new(),
+            "Add a word".to_string(),
+        ));
+        let json = to_sidecar_json(&doc).unwrap();
+
+        // Text inserted before the point moves it along
+        let mut edited = Document::new("Test".to_string(), "zero one two\nthree".to_string());
+        assert_eq!(apply_sidecar(&mut edited, &json).unwrap(), 0);
+        assert_eq!(edited.annotations[0].range, TextRange::new(9, 9));
+
+        // A point whose neighbouring words changed is orphaned
+        let mut edited = Document::new("Test".to_string(), "one 2\nthree".to_string());
+        assert_eq!(apply_sidecar(&mut edited, &json).unwrap(), 1);
+        assert!(edited.annotations.is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+
+use crate::model::{Annotation, Category};
+
+/// Sidebar sort order for annotations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Position,
+    Severity,
+    Category,
+    Created,
+    Author,
+}
+
+impl SortOrder {
+    pub fn all() -> &'static [SortOrder] {
+        &[
+            SortOrder::Position,
+            SortOrder::Severity,
+            SortOrder::Category,
+            SortOrder::Created,
+            SortOrder::Author,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Position => "Position",
+            SortOrder::Severity => "Severity",
+            SortOrder::Category => "Category",
+            SortOrder::Created => "Created",
+            SortOrder::Author => "Author",
+        }
+    }
+
+    /// Next order in the cycle, wrapping around
+    pub fn next(&self) -> SortOrder {
+        let all = Self::all();
+        let idx = all.iter().position(|o| o == self).unwrap_or(0);
+        all[(idx + 1) % all.len()]
+    }
+
+    /// Compare two annotations, breaking ties by position, with insertion
+    /// points ahead of spans starting at the same offset. General comments on
+    /// the document or a section always come first.
+    pub fn compare(&self, a: &Annotation, b: &Annotation) -> Ordering {
+        let scope = a.scope.rank().cmp(&b.scope.rank());
+        let primary = match self {
+            SortOrder::Position => Ordering::Equal,
+            SortOrder::Severity => a.severity.rank().cmp(&b.severity.rank()),
+            SortOrder::Category => category_rank(a.category).cmp(&category_rank(b.category)),
+            SortOrder::Created => a.created_at.cmp(&b.created_at),
+            // Annotations without an author sort last
+            SortOrder::Author => match (&a.author, &b.author) {
+                (Some(x), Some(y)) => x.to_lowercase().cmp(&y.to_lowercase()),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        };
+        scope
+            .then(primary)
+            .then(a.range.start_offset.cmp(&b.range.start_offset))
+            .then(a.range.end_offset.cmp(&b.range.end_offset))
+    }
+
+    /// Label of the sidebar group an annotation belongs to, if this order groups
+    pub fn group_label(&self, ann: &Annotation) -> Option<String> {
+        match self {
+            SortOrder::Position => None,
+            _ if ann.is_general() => Some("General".to_string()),
+            SortOrder::Severity => Some(ann.severity.as_str().to_string()),
+            SortOrder::Category => Some(
+                ann.category
+                    .map(|c| c.as_str())
+                    .unwrap_or("Uncategorized")
+                    .to_string(),
+            ),
+            SortOrder::Created if ann.created_unknown() => Some("Unknown date".to_string()),
+            SortOrder::Created => Some(ann.created_at.format("%Y-%m-%d").to_string()),
+            SortOrder::Author => Some(ann.author.clone().unwrap_or_else(|| "Unknown".to_string())),
+        }
+    }
+}
+
+fn category_rank(category: Option<Category>) -> usize {
+    category
+        .and_then(|c| Category::all().iter().position(|x| *x == c))
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Document, Severity, TextRange};
+
+    #[test]
+    fn test_sort_by_severity_then_position() {
+        let mut doc = Document::new("Test".to_string(), "abcdefghij".to_string());
+        for (start, severity) in [
+            (6, Severity::MustFix),
+            (0, Severity::Consider),
+            (2, Severity::MustFix),
+        ] {
+            let mut ann = Annotation::new(
+                TextRange::new(start, start + 1),
+                String::new(),
+                String::new(),
+            );
+            ann.severity = severity;
+            doc.add_annotation(ann);
+        }
+
+        let starts: Vec<_> = doc
+            .annotations_sorted_by(SortOrder::Severity)
+            .iter()
+            .map(|a| a.range.start_offset)
+            .collect();
+        assert_eq!(starts, vec![2, 6, 0]);
+
+        let positions: Vec<_> = doc
+            .annotations_sorted()
+            .iter()
+            .map(|a| a.range.start_offset)
+            .collect();
+        assert_eq!(positions, vec![0, 2, 6]);
+    }
+
+    #[test]
+    fn test_group_label() {
+        let ann = Annotation::new(TextRange::new(0, 1), String::new(), String::new());
+        assert_eq!(SortOrder::Position.group_label(&ann), None);
+        assert_eq!(
+            SortOrder::Category.group_label(&ann).as_deref(),
+            Some("Uncategorized")
+        );
+        assert_eq!(
+            SortOrder::Author.group_label(&ann).as_deref(),
+            Some("Unknown")
+        );
+
+        // Annotations saved without a creation time load with a fixed one
+        let mut json = serde_json::to_value(&ann).unwrap();
+        json.as_object_mut().unwrap().remove("created_at");
+        let legacy: Annotation = serde_json::from_value(json).unwrap();
+        assert!(legacy.created_unknown());
+        assert_eq!(
+            SortOrder::Created.group_label(&legacy).as_deref(),
+            Some("Unknown date")
+        );
+    }
+}
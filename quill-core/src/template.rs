@@ -0,0 +1,791 @@
+//! Prompt templates with placeholders and sections
+//!
+//! Templates use a small Mustache-like syntax:
+//!
+//! - `{{name}}` inserts a value, `{{name | json}}` inserts it as a JSON string
+//! - `{{#name}}...{{/name}}` repeats for each item of a list, or renders once
+//!   when a flag is set or a value is non-empty
+//! - `{{^name}}...{{/name}}` renders when a list is empty or a value is unset
+//! - `{{! comment }}` is ignored
+//!
+//! Section tags alone on a line take the whole line with them.
+//!
+//! Document values: `title`, `filename`, `filepath`, `content`, `word_count`,
+//! `count`, `full_text`, `has_annotations`, `has_excerpts`, `has_insertions`,
+//! `has_general`, `has_feedback`, `general_count`, `structured`, and the lists
+//! `annotations`, `severities`, `categories`, `excerpts` and `general`. Groups
+//! have `severity` or `category`, `count` and `annotations`. General comments
+//! on the document or a section have `id`, `label`, `scope`, `heading`,
+//! `comment`, `category`, `severity` and `severity_name`. Annotations have
+//! `id`, `text`, `comment`, `suggestion`, `has_suggestion`, `category`,
+//! `severity`, `severity_name`, `line`, `start`, `end`, `first` and `last`;
+//! insertion points also set `is_point` and `insert_at` (`Insert at line 3,
+//! after "..."`). Excerpts have `start_line`, `end_line` and `lines` (numbered). Prompts
+//! split to fit a token budget also have `section`, `part` and `parts`.
+
+use std::ops::Range;
+
+use crate::excerpt::{excerpt_ranges, number_lines};
+use crate::export::{
+    estimate_tokens, general_label, insertion_point, section_title, PromptOptions,
+};
+use crate::model::{Annotation, Category, Document, Severity};
+use crate::outline::parse_headings;
+
+/// The built-in prompt format
+pub const DEFAULT_TEMPLATE: &str = r#"## Document: {{title}}{{#section}} (part {{part}} of {{parts}}: {{section}}){{/section}}
+
+Please review and edit this document based on the following annotations.
+
+{{#full_text}}
+### Full Text
+
+{{content}}
+
+---
+
+{{/full_text}}
+{{#has_excerpts}}
+### Excerpts
+
+{{#excerpts}}
+Lines {{start_line}}-{{end_line}}:
+{{lines}}
+
+{{/excerpts}}
+---
+
+{{/has_excerpts}}
+{{^has_annotations}}
+{{^has_general}}
+No annotations to address.
+{{/has_general}}
+{{/has_annotations}}
+{{#has_general}}
+### General Comments ({{general_count}} items)
+
+{{#general}}
+**{{label}}** ({{severity}})
+{{#category}}
+- Category: {{category}}
+{{/category}}
+- Feedback: {{comment}}
+
+{{/general}}
+---
+
+{{/has_general}}
+{{#has_annotations}}
+### Annotations ({{count}} items)
+
+{{#severities}}
+#### {{severity}} ({{count}})
+
+{{#annotations}}
+{{#is_point}}**{{insert_at}}**{{/is_point}}{{^is_point}}**"{{text}}"**{{#has_excerpts}} (line {{line}}){{/has_excerpts}}{{/is_point}}
+{{#structured}}
+- ID: {{id}}
+{{/structured}}
+{{#category}}
+- Category: {{category}}
+{{/category}}
+- Feedback: {{comment}}
+{{#has_suggestion}}
+- **Suggested edit: {{#suggestion}}"{{suggestion}}"{{/suggestion}}{{^suggestion}}delete this text{{/suggestion}}**
+{{/has_suggestion}}
+
+{{/annotations}}
+{{/severities}}
+---
+
+{{/has_annotations}}
+{{#has_feedback}}
+{{#structured}}
+Reply with a JSON array in a ```json code block, with one object per annotation you change:
+
+```json
+[{"id": "<annotation ID>", "revised": "<replacement for the quoted text>", "explanation": "<what changed and why>"}]
+```
+
+Use the IDs listed above, replace only the quoted text, and leave out annotations you don't change.{{#has_insertions}} For an insertion point, "revised" is the text to insert there.{{/has_insertions}}{{/structured}}{{^structured}}Please provide the revised document with all annotations addressed. For each change, briefly note what was modified and why.{{/structured}}{{/has_feedback}}"#;
+
+/// Name of the built-in template
+pub const DEFAULT_TEMPLATE_NAME: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Var {
+        name: String,
+        json: bool,
+    },
+    Section {
+        name: String,
+        inverted: bool,
+        body: Vec<Node>,
+    },
+}
+
+/// A parsed prompt template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    nodes: Vec<Node>,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE_NAME, DEFAULT_TEMPLATE).expect("default template parses")
+    }
+}
+
+impl Template {
+    pub fn parse(name: &str, source: &str) -> Result<Self, String> {
+        let mut stack: Vec<(String, bool, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+
+        for token in tokenize(source)? {
+            let tag = match token {
+                Token::Text(text) => {
+                    nodes.push(Node::Text(text));
+                    continue;
+                }
+                Token::Tag(tag) => tag,
+            };
+
+            if let Some(section) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+                let inverted = tag.starts_with('^');
+                stack.push((
+                    section.trim().to_string(),
+                    inverted,
+                    std::mem::take(&mut nodes),
+                ));
+            } else if let Some(close) = tag.strip_prefix('/') {
+                let close = close.trim();
+                let (name, inverted, parent) = stack
+                    .pop()
+                    .ok_or_else(|| format!("Unexpected {{{{/{}}}}}", close))?;
+                if name != close {
+                    return Err(format!(
+                        "Expected {{{{/{}}}}}, found {{{{/{}}}}}",
+                        name, close
+                    ));
+                }
+                let body = std::mem::replace(&mut nodes, parent);
+                nodes.push(Node::Section {
+                    name,
+                    inverted,
+                    body,
+                });
+            } else if !tag.starts_with('!') {
+                let (name, filter) = match tag.split_once('|') {
+                    Some((name, filter)) => (name.trim(), Some(filter.trim())),
+                    None => (tag.as_str(), None),
+                };
+                let json = match filter {
+                    None => false,
+                    Some("json") => true,
+                    Some(other) => return Err(format!("Unknown filter: {}", other)),
+                };
+                nodes.push(Node::Var {
+                    name: name.to_string(),
+                    json,
+                });
+            }
+        }
+
+        if let Some((name, _, _)) = stack.pop() {
+            return Err(format!("Unclosed section {{{{#{}}}}}", name));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            nodes,
+        })
+    }
+
+    /// Render the template for a document's unresolved annotations
+    pub fn render(&self, doc: &Document, options: &PromptOptions) -> String {
+        self.render_part(doc, options, None)
+    }
+
+    /// Render one prompt, or several covering consecutive sections when the
+    /// whole prompt is over the token budget. A section over the budget by
+    /// itself is split at its paragraphs; see [`crate::export::budget_warning`]
+    /// for parts that are still over.
+    pub fn render_all(&self, doc: &Document, options: &PromptOptions) -> Vec<String> {
+        let whole = self.render(doc, options);
+        let Some(budget) = options.token_budget else {
+            return vec![whole];
+        };
+        if estimate_tokens(&whole) <= budget {
+            return vec![whole];
+        }
+
+        let annotated = |part: &Part| {
+            doc.annotations.iter().any(|a| {
+                !a.is_resolved && !is_document_wide(a) && part.range.contains(&a.range.start_offset)
+            })
+        };
+        let fits =
+            |part: &Part| estimate_tokens(&self.render_part(doc, options, Some(part))) <= budget;
+
+        // Group sections with unresolved annotations while they fit the
+        // budget, splitting a section too long by itself at its paragraphs
+        let mut groups: Vec<Part> = Vec::new();
+        for section in sections(doc).into_iter().filter(|s| annotated(s)) {
+            let pieces = if fits(&section) {
+                vec![section]
+            } else {
+                paragraphs(doc, section)
+            };
+            for part in pieces.into_iter().filter(|p| annotated(p)) {
+                if let Some(last) = groups.last_mut() {
+                    let merged = Part {
+                        range: last.range.start..part.range.end,
+                        ..last.clone()
+                    };
+                    if fits(&merged) {
+                        *last = merged;
+                        continue;
+                    }
+                }
+                groups.push(part);
+            }
+        }
+
+        if groups.is_empty() {
+            return vec![whole];
+        }
+        let count = groups.len();
+        groups
+            .into_iter()
+            .enumerate()
+            .map(|(i, part)| {
+                let part = Part {
+                    index: i + 1,
+                    count,
+                    ..part
+                };
+                self.render_part(doc, options, Some(&part))
+            })
+            .collect()
+    }
+
+    fn render_part(&self, doc: &Document, options: &PromptOptions, part: Option<&Part>) -> String {
+        let scope = document_scope(doc, options, part);
+        let mut out = String::new();
+        render_nodes(&self.nodes, &[&scope], &mut out);
+        out
+    }
+}
+
+/// A run of sections rendered as one of several prompts
+#[derive(Debug, Clone)]
+struct Part {
+    range: Range<usize>,
+    section: String,
+    index: usize,
+    count: usize,
+}
+
+/// Consecutive spans of the document, split at every heading
+fn sections(doc: &Document) -> Vec<Part> {
+    let headings = parse_headings(&doc.content);
+    let mut starts: Vec<(usize, String)> = headings
+        .iter()
+        .map(|h| (h.start_offset, h.title.clone()))
+        .collect();
+    if starts.first().is_none_or(|(start, _)| *start > 0) {
+        starts.insert(0, (0, "Preamble".to_string()));
+    }
+
+    let ends: Vec<usize> = starts
+        .iter()
+        .skip(1)
+        .map(|(start, _)| *start)
+        .chain([doc.content.len()])
+        .collect();
+    starts
+        .into_iter()
+        .zip(ends)
+        .map(|((start, section), end)| Part {
+            range: start..end,
+            section,
+            index: 0,
+            count: 0,
+        })
+        .collect()
+}
+
+/// A section split after each blank line, keeping its title
+fn paragraphs(doc: &Document, section: Part) -> Vec<Part> {
+    let Range { start, end } = section.range;
+    let text = &doc.content[start..end];
+    let mut starts = vec![start];
+    starts.extend(text.match_indices("\n\n").filter_map(|(i, _)| {
+        // The next paragraph starts after any further blank lines
+        let rest = &text[i..];
+        let skipped = rest.len() - rest.trim_start_matches('\n').len();
+        (i + skipped < text.len()).then_some(start + i + skipped)
+    }));
+    starts.dedup();
+    let ends: Vec<usize> = starts.iter().skip(1).copied().chain([end]).collect();
+    starts
+        .into_iter()
+        .zip(ends)
+        .map(|(start, end)| Part {
+            range: start..end,
+            ..section.clone()
+        })
+        .collect()
+}
+
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(found) = source[pos..].find("{{") {
+        let open = pos + found;
+        let close = source[open + 2..]
+            .find("}}")
+            .map(|i| open + 2 + i)
+            .ok_or_else(|| format!("Unclosed tag on line {}", line_of(source, open)))?;
+        let tag = source[open + 2..close].trim().to_string();
+        let mut text_end = open;
+        let mut next = close + 2;
+
+        // A section or comment tag alone on its line removes the line
+        if tag.starts_with(['#', '^', '/', '!']) {
+            let line_start = source[..open].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[next..]
+                .find('\n')
+                .map_or(source.len(), |i| next + i + 1);
+            if line_start >= pos
+                && source[line_start..open].trim().is_empty()
+                && source[next..line_end].trim().is_empty()
+            {
+                text_end = line_start;
+                next = line_end;
+            }
+        }
+
+        if text_end > pos {
+            tokens.push(Token::Text(source[pos..text_end].to_string()));
+        }
+        tokens.push(Token::Tag(tag));
+        pos = next;
+    }
+
+    if pos < source.len() {
+        tokens.push(Token::Text(source[pos..].to_string()));
+    }
+    Ok(tokens)
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+enum Value {
+    Text(String),
+    Flag(bool),
+    List(Vec<Scope>),
+}
+
+impl Value {
+    fn is_set(&self) -> bool {
+        match self {
+            Value::Text(text) => !text.is_empty(),
+            Value::Flag(flag) => *flag,
+            Value::List(items) => !items.is_empty(),
+        }
+    }
+}
+
+type Scope = Vec<(&'static str, Value)>;
+
+fn lookup<'a>(scopes: &[&'a Scope], name: &str) -> Option<&'a Value> {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.iter().find(|(key, _)| *key == name).map(|(_, v)| v))
+}
+
+fn render_nodes(nodes: &[Node], scopes: &[&Scope], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var { name, json } => {
+                let text = match lookup(scopes, name) {
+                    Some(Value::Text(text)) => text.clone(),
+                    Some(Value::Flag(flag)) => flag.to_string(),
+                    _ => String::new(),
+                };
+                if *json {
+                    out.push_str(&serde_json::Value::String(text).to_string());
+                } else {
+                    out.push_str(&text);
+                }
+            }
+            Node::Section {
+                name,
+                inverted,
+                body,
+            } => {
+                let value = lookup(scopes, name);
+                let set = value.is_some_and(Value::is_set);
+                if *inverted {
+                    if !set {
+                        render_nodes(body, scopes, out);
+                    }
+                } else if let Some(Value::List(items)) = value {
+                    for item in items {
+                        let mut inner = scopes.to_vec();
+                        inner.push(item);
+                        render_nodes(body, &inner, out);
+                    }
+                } else if set {
+                    render_nodes(body, scopes, out);
+                }
+            }
+        }
+    }
+}
+
+fn text(value: impl ToString) -> Value {
+    Value::Text(value.to_string())
+}
+
+fn document_scope(doc: &Document, options: &PromptOptions, part: Option<&Part>) -> Scope {
+    let range = part.map_or(0..doc.content.len(), |p| p.range.clone());
+    // Comments on the whole document go in every part
+    let (mut general, unresolved): (Vec<&Annotation>, Vec<&Annotation>) = doc
+        .annotations
+        .iter()
+        .filter(|a| {
+            !a.is_resolved
+                && (part.is_none() || is_document_wide(a) || range.contains(&a.range.start_offset))
+        })
+        .partition(|a| a.is_general());
+    general.sort_by_key(|a| (a.scope.rank(), a.range.start_offset));
+    let general_items: Vec<Scope> = general
+        .iter()
+        .map(|ann| {
+            let document_wide = is_document_wide(ann);
+            vec![
+                ("id", text(ann.id)),
+                ("label", text(general_label(doc, ann))),
+                (
+                    "scope",
+                    text(if document_wide { "document" } else { "section" }),
+                ),
+                (
+                    "heading",
+                    text(if document_wide {
+                        String::new()
+                    } else {
+                        section_title(doc, ann)
+                    }),
+                ),
+                ("comment", text(&ann.comment)),
+                ("category", text(ann.category.map_or("", |c| c.as_str()))),
+                ("severity", text(ann.severity.as_str())),
+                ("severity_name", text(ann.severity.name())),
+            ]
+        })
+        .collect();
+    let content = match part {
+        Some(_) => doc.content[range].trim_end_matches('\n'),
+        None => &doc.content,
+    };
+
+    let excerpts: Vec<Scope> = match options.excerpts {
+        Some(context) => excerpt_ranges(
+            &doc.content,
+            unresolved
+                .iter()
+                .map(|a| a.range.start_offset..a.range.end_offset),
+            context,
+        )
+        .into_iter()
+        .map(|range| {
+            let start_line = doc.line_number(range.start);
+            vec![
+                ("start_line", text(start_line)),
+                ("end_line", text(doc.line_number(range.end))),
+                ("lines", text(number_lines(&doc.content[range], start_line))),
+            ]
+        })
+        .collect(),
+        None => Vec::new(),
+    };
+
+    let severities = Severity::all()
+        .iter()
+        .filter_map(|&severity| {
+            let items: Vec<_> = unresolved
+                .iter()
+                .copied()
+                .filter(|a| a.severity == severity)
+                .collect();
+            (!items.is_empty()).then(|| {
+                vec![
+                    ("severity", text(severity.as_str())),
+                    ("severity_name", text(severity.name())),
+                    ("count", text(items.len())),
+                    ("annotations", annotation_list(doc, &items)),
+                ]
+            })
+        })
+        .collect();
+
+    let mut category_keys: Vec<Option<Category>> =
+        Category::all().iter().copied().map(Some).collect();
+    category_keys.push(None);
+    let categories = category_keys
+        .into_iter()
+        .filter_map(|category| {
+            let items: Vec<_> = unresolved
+                .iter()
+                .copied()
+                .filter(|a| a.category == category)
+                .collect();
+            (!items.is_empty()).then(|| {
+                vec![
+                    (
+                        "category",
+                        text(category.map_or("Uncategorized", |c| c.as_str())),
+                    ),
+                    ("count", text(items.len())),
+                    ("annotations", annotation_list(doc, &items)),
+                ]
+            })
+        })
+        .collect();
+
+    vec![
+        ("title", text(&doc.title)),
+        ("filename", text(doc.filename.as_deref().unwrap_or(""))),
+        ("filepath", text(doc.filepath.as_deref().unwrap_or(""))),
+        ("content", text(content)),
+        ("word_count", text(doc.word_count())),
+        ("count", text(unresolved.len())),
+        (
+            "full_text",
+            Value::Flag(options.full_text && options.excerpts.is_none()),
+        ),
+        ("has_annotations", Value::Flag(!unresolved.is_empty())),
+        ("has_general", Value::Flag(!general_items.is_empty())),
+        (
+            "has_feedback",
+            Value::Flag(!unresolved.is_empty() || !general_items.is_empty()),
+        ),
+        ("general_count", text(general_items.len())),
+        ("has_excerpts", Value::Flag(!excerpts.is_empty())),
+        (
+            "has_insertions",
+            Value::Flag(unresolved.iter().any(|a| a.range.is_point())),
+        ),
+        // Only range annotations can be revised by ID
+        (
+            "structured",
+            Value::Flag(options.structured && !unresolved.is_empty()),
+        ),
+        ("annotations", annotation_list(doc, &unresolved)),
+        ("severities", Value::List(severities)),
+        ("categories", Value::List(categories)),
+        ("excerpts", Value::List(excerpts)),
+        ("general", Value::List(general_items)),
+        ("section", text(part.map_or("", |p| p.section.as_str()))),
+        ("part", text(part.map_or(0, |p| p.index))),
+        ("parts", text(part.map_or(0, |p| p.count))),
+    ]
+}
+
+fn is_document_wide(ann: &Annotation) -> bool {
+    ann.scope == crate::model::Scope::Document
+}
+
+fn annotation_list(doc: &Document, annotations: &[&Annotation]) -> Value {
+    let last = annotations.len().saturating_sub(1);
+    Value::List(
+        annotations
+            .iter()
+            .enumerate()
+            .map(|(i, ann)| {
+                let point = ann.range.is_point();
+                vec![
+                    ("id", text(ann.id)),
+                    ("text", text(&ann.selected_text)),
+                    ("comment", text(&ann.comment)),
+                    ("suggestion", text(ann.suggestion.as_deref().unwrap_or(""))),
+                    ("has_suggestion", Value::Flag(ann.has_suggestion())),
+                    ("category", text(ann.category.map_or("", |c| c.as_str()))),
+                    ("severity", text(ann.severity.as_str())),
+                    ("severity_name", text(ann.severity.name())),
+                    ("line", text(doc.line_number(ann.range.start_offset))),
+                    ("start", text(ann.range.start_offset)),
+                    ("end", text(ann.range.end_offset)),
+                    ("is_point", Value::Flag(point)),
+                    (
+                        "insert_at",
+                        text(if point {
+                            insertion_point(doc, ann)
+                        } else {
+                            String::new()
+                        }),
+                    ),
+                    ("first", Value::Flag(i == 0)),
+                    ("last", Value::Flag(i == last)),
+                ]
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TextRange;
+
+    #[test]
+    fn test_template_sections_and_json() {
+        let mut doc = Document::new("Notes".to_string(), "one two three".to_string());
+        let mut ann = Annotation::new(
+            TextRange::new(0, 3),
+            "one".to_string(),
+            "Say \"1\"".to_string(),
+        );
+        ann.category = Some(Category::Voice);
+        doc.add_annotation(ann);
+        doc.add_annotation(Annotation::new(
+            TextRange::new(4, 7),
+            "two".to_string(),
+            "cut".to_string(),
+        ));
+
+        let source = "[\n{{#annotations}}\n  {\"text\": {{text | json}}, \"comment\": {{comment | json}}}{{^last}},{{/last}}\n{{/annotations}}\n]\n";
+        let template = Template::parse("json", source).unwrap();
+        let rendered = template.render(&doc, &PromptOptions::default());
+        let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(parsed[0]["comment"], "Say \"1\"");
+        assert_eq!(parsed[1]["text"], "two");
+
+        let template = Template::parse(
+            "cats",
+            "{{#categories}}{{category}}: {{count}}\n{{/categories}}",
+        )
+        .unwrap();
+        assert_eq!(
+            template.render(&doc, &PromptOptions::default()),
+            "Voice: 1\nUncategorized: 1\n"
+        );
+
+        assert!(Template::parse("bad", "{{#annotations}}").is_err());
+        assert!(Template::parse("bad", "{{#a}}{{/b}}").is_err());
+        assert!(Template::parse("bad", "{{title | upper}}").is_err());
+    }
+
+    #[test]
+    fn test_excerpts_and_budget_chunks() {
+        let intro = "Intro text that nobody flagged.\n\n";
+        let one = format!(
+            "# One\n\n{}\n\n",
+            "Filler sentence here. ".repeat(40).trim_end()
+        );
+        let two = format!(
+            "# Two\n\n{}\n",
+            "More filler text now. ".repeat(40).trim_end()
+        );
+        let content = format!("{}{}{}", intro, one, two);
+        let mut doc = Document::new("Book".to_string(), content.clone());
+        for heading in ["# One", "# Two"] {
+            let start = content.find(heading).unwrap() + heading.len() + 2;
+            let text = content[start..start + 6].to_string();
+            doc.add_annotation(Annotation::new(
+                TextRange::new(start, start + 6),
+                text,
+                "fix".to_string(),
+            ));
+        }
+
+        let options = PromptOptions {
+            excerpts: Some(crate::excerpt::ExcerptContext::Paragraphs(0)),
+            ..PromptOptions::default()
+        };
+        let prompt = Template::default().render(&doc, &options);
+        assert!(!prompt.contains("### Full Text"));
+        assert!(!prompt.contains("Intro text"));
+        assert!(prompt.contains("Lines 5-5:\n5 | Filler"));
+        assert!(prompt.contains("**\"Filler\"** (line 5)"));
+
+        let budget =
+            estimate_tokens(&Template::default().render(&doc, &PromptOptions::default())) - 50;
+        let options = PromptOptions {
+            token_budget: Some(budget),
+            ..PromptOptions::default()
+        };
+        let parts = Template::default().render_all(&doc, &options);
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].starts_with("## Document: Book (part 1 of 2: One)"));
+        assert!(parts[1].contains("More filler") && !parts[1].contains("Filler sentence"));
+        assert!(parts
+            .iter()
+            .all(|p| p.contains("### Annotations (1 items)")));
+    }
+
+    #[test]
+    fn test_budget_splits_long_section_at_paragraphs() {
+        let one = "Filler sentence here. ".repeat(40);
+        let two = "More filler text now. ".repeat(40);
+        let content = format!("# One\n\n{}\n\n\n{}\n", one.trim_end(), two.trim_end());
+        let mut doc = Document::new("Book".to_string(), content.clone());
+        for start in [
+            content.find("Filler").unwrap(),
+            content.find("More").unwrap(),
+        ] {
+            let text = content[start..start + 6].to_string();
+            doc.add_annotation(Annotation::new(
+                TextRange::new(start, start + 6),
+                text,
+                "fix".to_string(),
+            ));
+        }
+
+        let whole = Template::default().render(&doc, &PromptOptions::default());
+        let options = PromptOptions {
+            token_budget: Some(estimate_tokens(&whole) - 50),
+            ..PromptOptions::default()
+        };
+        let parts = Template::default().render_all(&doc, &options);
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].starts_with("## Document: Book (part 1 of 2: One)"));
+        assert!(parts[1].starts_with("## Document: Book (part 2 of 2: One)"));
+        assert!(parts[1].contains("More filler") && !parts[1].contains("Filler sentence"));
+        assert_eq!(
+            crate::export::budget_warning(&parts, options.token_budget),
+            None
+        );
+
+        // A paragraph longer than the budget can't be split, so it's reported
+        let options = PromptOptions {
+            token_budget: Some(100),
+            ..PromptOptions::default()
+        };
+        let parts = Template::default().render_all(&doc, &options);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(
+            crate::export::budget_warning(&parts, options.token_budget).as_deref(),
+            Some("parts 1, 2 of 2 are over the 100-token budget")
+        );
+        assert_eq!(
+            crate::export::budget_warning(&parts[..1], Some(100)).as_deref(),
+            Some("the prompt is over the 100-token budget")
+        );
+    }
+}
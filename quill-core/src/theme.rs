@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::model::Severity;
+
+/// A palette color, independent of the rendering backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+    /// The terminal's own default color
+    Reset,
+    Rgb(u8, u8, u8),
+    /// One of the 16 standard terminal colors (0-15)
+    Ansi(u8),
+}
+
+/// Names of the 16 standard terminal colors, in index order
+const ANSI_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "gray",
+    "darkgray",
+    "lightred",
+    "lightgreen",
+    "lightyellow",
+    "lightblue",
+    "lightmagenta",
+    "lightcyan",
+    "white",
+];
+
+impl FromStr for ThemeColor {
+    type Err = String;
+
+    /// Parse `#rrggbb`, `default`, an ANSI color name, or an ANSI index 0-15
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        if lower == "default" || lower == "reset" {
+            return Ok(ThemeColor::Reset);
+        }
+        if let Some(hex) = lower.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            if hex.len() == 6 {
+                if let (Some(r), Some(g), Some(b)) = (channel(0), channel(2), channel(4)) {
+                    return Ok(ThemeColor::Rgb(r, g, b));
+                }
+            }
+        }
+        if let Some(index) = ANSI_NAMES.iter().position(|n| *n == lower) {
+            return Ok(ThemeColor::Ansi(index as u8));
+        }
+        match lower.parse::<u8>() {
+            Ok(index) if index < 16 => Ok(ThemeColor::Ansi(index)),
+            _ => Err(format!("Invalid color: {}", s)),
+        }
+    }
+}
+
+/// Named color palette used by both frontends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub background: ThemeColor,
+    pub surface0: ThemeColor,
+    pub surface1: ThemeColor,
+    pub text: ThemeColor,
+    pub subtext0: ThemeColor,
+    pub red: ThemeColor,
+    pub yellow: ThemeColor,
+    pub green: ThemeColor,
+    pub blue: ThemeColor,
+    pub mauve: ThemeColor,
+    pub teal: ThemeColor,
+}
+
+impl Theme {
+    /// Catppuccin Mocha (dark, default)
+    pub fn mocha() -> Self {
+        use ThemeColor::*;
+        Self {
+            name: "mocha".to_string(),
+            background: Reset,
+            surface0: Rgb(49, 50, 68),
+            surface1: Rgb(69, 71, 90),
+            text: Rgb(205, 214, 244),
+            subtext0: Rgb(166, 173, 200),
+            red: Rgb(243, 139, 168),
+            yellow: Rgb(249, 226, 175),
+            green: Rgb(166, 227, 161),
+            blue: Rgb(137, 180, 250),
+            mauve: Rgb(203, 166, 247),
+            teal: Rgb(148, 226, 213),
+        }
+    }
+
+    /// Catppuccin Latte (light)
+    pub fn latte() -> Self {
+        use ThemeColor::*;
+        Self {
+            name: "latte".to_string(),
+            background: Rgb(239, 241, 245),
+            surface0: Rgb(204, 208, 218),
+            surface1: Rgb(188, 192, 204),
+            text: Rgb(76, 79, 105),
+            subtext0: Rgb(108, 111, 133),
+            red: Rgb(210, 15, 57),
+            yellow: Rgb(223, 142, 29),
+            green: Rgb(64, 160, 43),
+            blue: Rgb(30, 102, 245),
+            mauve: Rgb(136, 57, 239),
+            teal: Rgb(23, 146, 153),
+        }
+    }
+
+    /// Pure colors on black for low-vision users
+    pub fn high_contrast() -> Self {
+        use ThemeColor::*;
+        Self {
+            name: "high-contrast".to_string(),
+            background: Rgb(0, 0, 0),
+            surface0: Rgb(48, 48, 48),
+            surface1: Rgb(96, 96, 96),
+            text: Rgb(255, 255, 255),
+            subtext0: Rgb(220, 220, 220),
+            red: Rgb(255, 90, 90),
+            yellow: Rgb(255, 255, 0),
+            green: Rgb(0, 255, 0),
+            blue: Rgb(90, 170, 255),
+            mauve: Rgb(255, 110, 255),
+            teal: Rgb(0, 255, 255),
+        }
+    }
+
+    /// Standard terminal colors, for terminals without truecolor
+    pub fn ansi16() -> Self {
+        use ThemeColor::*;
+        Self {
+            name: "16-color".to_string(),
+            background: Reset,
+            surface0: Ansi(0),
+            surface1: Ansi(8),
+            text: Reset,
+            subtext0: Ansi(7),
+            red: Ansi(1),
+            yellow: Ansi(3),
+            green: Ansi(2),
+            blue: Ansi(4),
+            mauve: Ansi(5),
+            teal: Ansi(6),
+        }
+    }
+
+    /// No colors at all (`NO_COLOR`); highlights fall back to text attributes
+    pub fn no_color() -> Self {
+        use ThemeColor::Reset;
+        Self {
+            name: "no-color".to_string(),
+            background: Reset,
+            surface0: Reset,
+            surface1: Reset,
+            text: Reset,
+            subtext0: Reset,
+            red: Reset,
+            yellow: Reset,
+            green: Reset,
+            blue: Reset,
+            mauve: Reset,
+            teal: Reset,
+        }
+    }
+
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Self::mocha(),
+            Self::latte(),
+            Self::high_contrast(),
+            Self::ansi16(),
+            Self::no_color(),
+        ]
+    }
+
+    /// Whether the theme has no colors, so highlights must use text attributes
+    pub fn is_monochrome(&self) -> bool {
+        [
+            self.background,
+            self.surface0,
+            self.surface1,
+            self.text,
+            self.subtext0,
+            self.red,
+            self.yellow,
+            self.green,
+            self.blue,
+            self.mauve,
+            self.teal,
+        ]
+        .iter()
+        .all(|c| *c == ThemeColor::Reset)
+    }
+
+    pub fn severity_color(&self, severity: Severity) -> ThemeColor {
+        match severity {
+            Severity::MustFix => self.red,
+            Severity::ShouldFix => self.yellow,
+            Severity::Consider => self.green,
+        }
+    }
+
+    /// Build a user theme from a spec, starting from the theme it extends
+    pub fn from_spec(name: &str, spec: &ThemeSpec, themes: &[Theme]) -> Result<Theme, String> {
+        let base = spec.extends.as_deref().unwrap_or("mocha");
+        let mut theme = themes
+            .iter()
+            .find(|t| t.name == base)
+            .cloned()
+            .ok_or_else(|| format!("Unknown theme: {}", base))?;
+        theme.name = name.to_string();
+
+        for (key, value) in &spec.colors {
+            let color = value.parse()?;
+            let slot = match key.as_str() {
+                "background" => &mut theme.background,
+                "surface0" => &mut theme.surface0,
+                "surface1" => &mut theme.surface1,
+                "text" => &mut theme.text,
+                "subtext0" => &mut theme.subtext0,
+                "red" => &mut theme.red,
+                "yellow" => &mut theme.yellow,
+                "green" => &mut theme.green,
+                "blue" => &mut theme.blue,
+                "mauve" => &mut theme.mauve,
+                "teal" => &mut theme.teal,
+                _ => return Err(format!("Unknown theme color: {}", key)),
+            };
+            *slot = color;
+        }
+        Ok(theme)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::mocha()
+    }
+}
+
+/// User theme definition from config: a base theme plus color overrides
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeSpec {
+    pub extends: Option<String>,
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!("#1e66f5".parse(), Ok(ThemeColor::Rgb(30, 102, 245)));
+        assert_eq!("LightRed".parse(), Ok(ThemeColor::Ansi(9)));
+        assert_eq!("4".parse(), Ok(ThemeColor::Ansi(4)));
+        assert_eq!("default".parse(), Ok(ThemeColor::Reset));
+        assert!("#12345".parse::<ThemeColor>().is_err());
+        assert!("16".parse::<ThemeColor>().is_err());
+    }
+
+    #[test]
+    fn test_user_theme_extends_builtin() {
+        let mut spec = ThemeSpec {
+            extends: Some("latte".to_string()),
+            ..Default::default()
+        };
+        spec.colors.insert("red".to_string(), "#ff0000".to_string());
+
+        let theme = Theme::from_spec("mine", &spec, &Theme::builtin()).unwrap();
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.red, ThemeColor::Rgb(255, 0, 0));
+        assert_eq!(theme.text, Theme::latte().text);
+
+        spec.colors
+            .insert("purple".to_string(), "#ffffff".to_string());
+        assert!(Theme::from_spec("mine", &spec, &Theme::builtin()).is_err());
+        assert!(Theme::no_color().is_monochrome() && !theme.is_monochrome());
+
+        // Any color left makes a no-color based theme colored
+        let spec = ThemeSpec {
+            extends: Some("no-color".to_string()),
+            colors: BTreeMap::from([("red".to_string(), "red".to_string())]),
+        };
+        let theme = Theme::from_spec("mine", &spec, &Theme::builtin()).unwrap();
+        assert!(!theme.is_monochrome());
+    }
+}
@@ -1,21 +1,31 @@
 //! Browser-based file I/O using Web APIs
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Result};
 use wasm_bindgen::prelude::*;
-use web_sys::{Blob, HtmlAnchorElement, Url};
+use web_sys::{Blob, FileReader, HtmlAnchorElement, HtmlInputElement, Url};
 
-use quill_core::Document;
+use quill_core::sidecar::{apply_sidecar, to_sidecar_json};
+use quill_core::{App, Document, Platform};
 
 /// Download JSON as a file
 pub fn download_json(filename: &str, json: &str) -> Result<(), JsValue> {
+    download_file(filename, json, "application/json")
+}
+
+/// Download text content as a file with the given MIME type
+pub fn download_file(filename: &str, contents: &str, mime_type: &str) -> Result<(), JsValue> {
     let window = web_sys::window().ok_or("No window")?;
     let document = window.document().ok_or("No document")?;
 
-    // Create a blob from the JSON content
+    // Create a blob from the content
     let blob_parts = js_sys::Array::new();
-    blob_parts.push(&JsValue::from_str(json));
+    blob_parts.push(&JsValue::from_str(contents));
 
     let blob_options = web_sys::BlobPropertyBag::new();
-    blob_options.set_type("application/json");
+    blob_options.set_type(mime_type);
 
     let blob = Blob::new_with_str_sequence_and_options(&blob_parts, &blob_options)?;
 
@@ -37,6 +47,39 @@ pub fn download_json(filename: &str, json: &str) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Write text to the clipboard with the async Clipboard API, calling `done`
+/// once the browser has accepted or refused it
+pub fn copy_to_clipboard(
+    text: &str,
+    done: impl FnOnce(Result<(), String>) + 'static,
+) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let promise = window.navigator().clipboard().write_text(text);
+
+    // Only one of the two callbacks runs
+    let done = Rc::new(RefCell::new(Some(done)));
+    let on_copied = {
+        let done = done.clone();
+        Closure::once(move |_: JsValue| {
+            let done = done.borrow_mut().take();
+            if let Some(done) = done {
+                done(Ok(()));
+            }
+        })
+    };
+    let on_error = Closure::once(move |e: JsValue| {
+        let done = done.borrow_mut().take();
+        if let Some(done) = done {
+            done(Err(e.as_string().unwrap_or_else(|| format!("{:?}", e))));
+        }
+    });
+    let _ = promise.then2(&on_copied, &on_error);
+    on_copied.forget();
+    on_error.forget();
+
+    Ok(())
+}
+
 /// Save document to localStorage
 pub fn save_to_storage(key: &str, doc: &Document) -> Result<(), JsValue> {
     let window = web_sys::window().ok_or("No window")?;
@@ -50,10 +93,162 @@ pub fn save_to_storage(key: &str, doc: &Document) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Save raw text to localStorage
+pub fn save_text_to_storage(key: &str, text: &str) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let storage = window.local_storage()?.ok_or("No localStorage")?;
+
+    storage.set_item(key, text)
+}
+
+/// localStorage key for a document's saved annotations
+fn sidecar_key(name: &str) -> String {
+    format!("quill-sidecar:{}", name)
+}
+
 /// Load document from localStorage
 pub fn load_from_storage(key: &str) -> Result<Option<String>, JsValue> {
     let window = web_sys::window().ok_or("No window")?;
     let storage = window.local_storage()?.ok_or("No localStorage")?;
 
-    Ok(storage.get_item(key)?)
+    storage.get_item(key)
+}
+
+/// Open a browser file picker for files matching `accept` and pass the chosen
+/// file's name and text to `on_load`
+pub fn pick_text_file(
+    accept: &str,
+    on_load: impl FnOnce(String, String) + 'static,
+) -> Result<(), JsValue> {
+    let window = web_sys::window().ok_or("No window")?;
+    let document = window.document().ok_or("No document")?;
+
+    let input: HtmlInputElement = document.create_element("input")?.dyn_into()?;
+    input.set_type("file");
+    input.set_accept(accept);
+
+    let picker = input.clone();
+    let on_change = Closure::once(move || {
+        let Some(file) = picker.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        let Ok(reader) = FileReader::new() else {
+            return;
+        };
+
+        let name = file.name();
+        let result = reader.clone();
+        let on_read = Closure::once(move || {
+            if let Some(text) = result.result().ok().and_then(|r| r.as_string()) {
+                on_load(name, text);
+            }
+        });
+        reader.set_onload(Some(on_read.as_ref().unchecked_ref()));
+        on_read.forget();
+
+        if let Err(e) = reader.read_as_text(&file) {
+            web_sys::console::error_1(&e);
+        }
+    });
+    input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+    on_change.forget();
+
+    input.click();
+    Ok(())
+}
+
+/// Platform I/O backed by browser APIs
+pub struct WebPlatform {
+    app: Rc<RefCell<App>>,
+}
+
+impl WebPlatform {
+    pub fn new(app: Rc<RefCell<App>>) -> Self {
+        Self { app }
+    }
+}
+
+impl Platform for WebPlatform {
+    fn open_file(&mut self, _path: &str) -> Result<Document> {
+        bail!("Opening files by path is not supported in the browser")
+    }
+
+    fn pick_file(&mut self) -> bool {
+        let app = self.app.clone();
+        let picked = pick_text_file(".md,.markdown,.txt,text/*", move |filename, content| {
+            let title = filename
+                .rsplit_once('.')
+                .map(|(stem, _)| stem.to_string())
+                .unwrap_or_else(|| filename.clone());
+            let mut doc =
+                Document::with_file_info(title, content, filename.clone(), filename.clone());
+
+            // Restore annotations saved with :w
+            if let Ok(Some(json)) = load_from_storage(&sidecar_key(&filename)) {
+                if let Err(e) = apply_sidecar(&mut doc, &json) {
+                    web_sys::console::error_1(&e.to_string().into());
+                }
+            }
+
+            app.borrow_mut().open_document(doc, &filename);
+        });
+        picked.is_ok()
+    }
+
+    fn pick_response(&mut self) -> bool {
+        let app = self.app.clone();
+        let picked = pick_text_file(".json,.md,.txt,text/*,application/json", move |_, text| {
+            app.borrow_mut().load_response(&text);
+        });
+        picked.is_ok()
+    }
+
+    fn pick_revision(&mut self) -> bool {
+        let app = self.app.clone();
+        let picked = pick_text_file(".md,.markdown,.txt,text/*", move |filename, text| {
+            app.borrow_mut().load_revision(&filename, text);
+        });
+        picked.is_ok()
+    }
+
+    fn save_export(&mut self, filename: &str, contents: &str) -> Result<String> {
+        let mime_type = match filename.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("json") => "application/json",
+            Some("md") => "text/markdown",
+            _ => "text/plain",
+        };
+        download_file(filename, contents, mime_type).map_err(|e| anyhow!("{:?}", e))?;
+        Ok(filename.to_string())
+    }
+
+    fn save_document(&mut self, doc: &Document) -> Result<String> {
+        let name = doc.filename.as_deref().unwrap_or(&doc.title);
+        download_file(name, &doc.content, "text/plain").map_err(|e| anyhow!("{:?}", e))?;
+        Ok(name.to_string())
+    }
+
+    fn save_sidecar(&mut self, doc: &Document) -> Result<String> {
+        let name = doc.filename.as_deref().unwrap_or(&doc.title);
+        let key = sidecar_key(name);
+        let json = to_sidecar_json(doc)?;
+        save_text_to_storage(&key, &json).map_err(|e| anyhow!("{:?}", e))?;
+        Ok(format!("browser storage ({})", key))
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str) -> Result<String> {
+        // Report the outcome once the browser settles the request
+        let app = self.app.clone();
+        let chars = text.chars().count();
+        copy_to_clipboard(text, move |result| {
+            let Ok(mut app) = app.try_borrow_mut() else {
+                return;
+            };
+            match result {
+                Ok(()) => app.set_status(&format!("Copied to clipboard ({} chars)", chars)),
+                Err(e) => app.set_status(&format!("Copying to clipboard failed: {}", e)),
+            }
+        })
+        .map_err(|e| anyhow!("{:?}", e))?;
+        Ok("sent to clipboard".to_string())
+    }
 }
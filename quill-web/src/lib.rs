@@ -6,11 +6,12 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use ratzilla::event::{KeyCode, KeyEvent};
 use ratzilla::ratatui::Terminal;
-use ratzilla::{event::KeyCode, DomBackend, WebRenderer};
+use ratzilla::{DomBackend, WebRenderer};
 use wasm_bindgen::prelude::*;
 
-use quill_core::{App, Category, Focus, InputTarget, Mode, Severity};
+use quill_core::{App, Config, Key, KeyChord};
 
 pub mod io;
 mod ui;
@@ -45,6 +46,9 @@ Try it out:
 4. Use 'e' to export your annotations
 "#;
 
+/// localStorage key holding the user configuration as JSON
+const CONFIG_KEY: &str = "quill-config";
+
 /// Initialize the Quill web application
 #[wasm_bindgen(start)]
 pub fn main() -> Result<(), JsValue> {
@@ -57,6 +61,17 @@ pub fn main() -> Result<(), JsValue> {
     app.load_document(doc);
     app.set_status("Welcome to Quill! Press 'v' to start selecting, '?' for help");
 
+    // Apply user configuration saved in localStorage
+    if let Ok(Some(json)) = io::load_from_storage(CONFIG_KEY) {
+        let errors = match Config::from_json(&json) {
+            Ok(config) => app.apply_config(&config),
+            Err(e) => vec![e.to_string()],
+        };
+        if let Some(error) = errors.first() {
+            app.set_status(&format!("Config: {}", error));
+        }
+    }
+
     // Wrap in Rc<RefCell> for shared state
     let app_state = Rc::new(RefCell::new(app));
 
@@ -69,19 +84,12 @@ pub fn main() -> Result<(), JsValue> {
     // Set up keyboard handler
     terminal.on_key_event({
         let app_state_cloned = app_state.clone();
+        let mut platform = io::WebPlatform::new(app_state.clone());
         move |event| {
-            let mut app = app_state_cloned.borrow_mut();
-            app.clear_status();
-
-            match app.mode {
-                Mode::Normal => handle_normal_mode(&mut app, event.code),
-                Mode::Visual => handle_visual_mode(&mut app, event.code),
-                Mode::Input => handle_input_mode(&mut app, event.code),
-                Mode::SeverityPicker => handle_severity_picker(&mut app, event.code),
-                Mode::CategoryPicker => handle_category_picker(&mut app, event.code),
-                Mode::Help => {
-                    app.mode = Mode::Normal;
-                }
+            if let Some(chord) = key_chord(&event) {
+                app_state_cloned
+                    .borrow_mut()
+                    .handle_key(chord, &mut platform);
             }
         }
     });
@@ -97,207 +105,29 @@ pub fn main() -> Result<(), JsValue> {
     Ok(())
 }
 
-fn handle_normal_mode(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Char('?') => app.mode = Mode::Help,
-
-        // Navigation
-        KeyCode::Char('j') | KeyCode::Down => {
-            if app.focus == Focus::Editor {
-                app.move_down();
-            } else {
-                app.next_annotation();
-            }
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            if app.focus == Focus::Editor {
-                app.move_up();
-            } else {
-                app.prev_annotation();
-            }
-        }
-        KeyCode::Char('h') | KeyCode::Left => {
-            app.move_left();
-        }
-        KeyCode::Char('l') | KeyCode::Right => {
-            app.move_right();
-        }
-        KeyCode::Char('g') => {
-            app.move_to_top();
-        }
-        KeyCode::Char('G') => {
-            app.move_to_bottom();
-        }
-
-        // Annotation navigation
-        KeyCode::Char(']') => app.next_annotation(),
-        KeyCode::Char('[') => app.prev_annotation(),
-
-        // Visual mode
-        KeyCode::Char('v') => app.enter_visual_mode(),
-
-        // Annotation actions
-        KeyCode::Char('d') => {
-            app.delete_selected_annotation();
-        }
-        KeyCode::Char('r') => {
-            app.toggle_selected_resolved();
-        }
-
-        // Focus toggle
-        KeyCode::Tab => app.toggle_focus(),
-
-        // Export
-        KeyCode::Char('e') => {
-            if let Some(doc) = &app.document {
-                match quill_core::to_json(doc) {
-                    Ok(json) => {
-                        if let Err(e) = io::download_json("quill-export.json", &json) {
-                            app.set_status(&format!("Export failed: {:?}", e));
-                        } else {
-                            app.set_status("Exported to quill-export.json");
-                        }
-                    }
-                    Err(e) => app.set_status(&format!("Serialization failed: {}", e)),
-                }
-            }
-        }
-
-        _ => {}
-    }
-}
-
-fn handle_visual_mode(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.selection_start = None;
-            app.selection_end = None;
-        }
-        KeyCode::Char('j') | KeyCode::Down => {
-            app.move_down();
-            app.update_selection();
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            app.move_up();
-            app.update_selection();
-        }
-        KeyCode::Char('h') | KeyCode::Left => {
-            app.move_left();
-            app.update_selection();
-        }
-        KeyCode::Char('l') | KeyCode::Right => {
-            app.move_right();
-            app.update_selection();
-        }
-        KeyCode::Char('w') => {
-            app.move_word_forward();
-            app.update_selection();
-        }
-        KeyCode::Char('b') => {
-            app.move_word_back();
-            app.update_selection();
-        }
-        KeyCode::Char('a') => {
-            app.start_annotation();
-        }
-        _ => {}
-    }
-}
-
-fn handle_input_mode(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.input_buffer.clear();
-            app.pending_range = None;
-        }
-        KeyCode::Enter => {
-            if app.input_target == InputTarget::Comment {
-                app.complete_annotation();
-            }
-        }
-        KeyCode::Backspace => {
-            app.input_buffer.pop();
-        }
-        KeyCode::Char(c) => {
-            app.input_buffer.push(c);
-        }
-        _ => {}
-    }
-}
-
-fn handle_severity_picker(app: &mut App, code: KeyCode) {
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.pending_range = None;
-        }
-        KeyCode::Char('j') | KeyCode::Down => {
-            app.severity_selected = (app.severity_selected + 1) % Severity::all().len();
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            let len = Severity::all().len();
-            app.severity_selected = if app.severity_selected == 0 {
-                len - 1
-            } else {
-                app.severity_selected - 1
-            };
-        }
-        KeyCode::Enter => {
-            app.pending_severity = Severity::all()[app.severity_selected];
-            app.mode = Mode::CategoryPicker;
-        }
-        KeyCode::Char('1') => {
-            app.pending_severity = Severity::MustFix;
-            app.mode = Mode::CategoryPicker;
-        }
-        KeyCode::Char('2') => {
-            app.pending_severity = Severity::ShouldFix;
-            app.mode = Mode::CategoryPicker;
-        }
-        KeyCode::Char('3') => {
-            app.pending_severity = Severity::Consider;
-            app.mode = Mode::CategoryPicker;
-        }
-        _ => {}
-    }
-}
-
-fn handle_category_picker(app: &mut App, code: KeyCode) {
-    let total = Category::all().len() + 1;
-
-    match code {
-        KeyCode::Esc => {
-            app.mode = Mode::Normal;
-            app.pending_range = None;
-        }
-        KeyCode::Char('j') | KeyCode::Down => {
-            app.category_selected = (app.category_selected + 1) % total;
-        }
-        KeyCode::Char('k') | KeyCode::Up => {
-            app.category_selected = if app.category_selected == 0 {
-                total - 1
-            } else {
-                app.category_selected - 1
-            };
-        }
-        KeyCode::Enter => {
-            app.pending_category = if app.category_selected == 0 {
-                None
-            } else {
-                Some(Category::all()[app.category_selected - 1])
-            };
-            app.input_buffer.clear();
-            app.input_target = InputTarget::Comment;
-            app.mode = Mode::Input;
-        }
-        KeyCode::Char('0') => {
-            app.pending_category = None;
-            app.input_buffer.clear();
-            app.input_target = InputTarget::Comment;
-            app.mode = Mode::Input;
-        }
-        _ => {}
-    }
+/// Convert a Ratzilla key event to a keymap chord
+fn key_chord(event: &KeyEvent) -> Option<KeyChord> {
+    let key = match event.code {
+        KeyCode::Char(c) => Key::Char(c),
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::F(n) => Key::F(n),
+        KeyCode::Unidentified => return None,
+    };
+    Some(KeyChord {
+        key,
+        ctrl: event.ctrl,
+        alt: event.alt,
+    })
 }